@@ -1,6 +1,7 @@
 use crate::point::Point;
 use crate::ray::Ray;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone)]
 pub struct AABB {
     pub minimum: Point,
@@ -42,4 +43,22 @@ impl AABB {
 
         AABB::new(small, big)
     }
+
+    /// Returns the box where `box0` and `box1` overlap, which is empty with its minimum above
+    /// its maximum if they do not.
+    pub fn overlap(box0: &AABB, box1: &AABB) -> AABB {
+        let small = Point::new(
+            f64::max(box0.minimum.0[0], box1.minimum.0[0]),
+            f64::max(box0.minimum.0[1], box1.minimum.0[1]),
+            f64::max(box0.minimum.0[2], box1.minimum.0[2]),
+        );
+
+        let big = Point::new(
+            f64::min(box0.maximum.0[0], box1.maximum.0[0]),
+            f64::min(box0.maximum.0[1], box1.maximum.0[1]),
+            f64::min(box0.maximum.0[2], box1.maximum.0[2]),
+        );
+
+        AABB::new(small, big)
+    }
 }
@@ -0,0 +1,166 @@
+use crate::color::Color;
+use crate::hit::HitRecord;
+use crate::material::Material;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+enum Perturbation {
+    NormalMap(Arc<dyn Texture + Sync + Send>),
+    BumpMap {
+        height: Arc<dyn Texture + Sync + Send>,
+        strength: f64,
+    },
+}
+
+/// Wraps a material to shade it with a normal perturbed by a texture, adding surface detail
+/// without extra geometry. The normal is perturbed in the tangent frame given by `dpdu` and
+/// `dpdv`, so objects without a parametrization are left as they are.
+pub struct Bumped {
+    material: Box<dyn Material + Sync + Send>,
+    perturbation: Perturbation,
+}
+
+impl Bumped {
+    /// Uses a tangent-space normal map, which stores the x, y and z components of the normal in
+    /// its red, green and blue channels as `0.5 * (n + 1)`, with z along the surface normal.
+    /// Image normal maps are data rather than colors, so create them with
+    /// `ImageTexture::from_image` without sRGB decoding.
+    pub fn normal_map(
+        material: impl Material + Sync + Send + 'static,
+        normal_map: impl Texture + Sync + Send + 'static,
+    ) -> Self {
+        Self {
+            material: Box::new(material),
+            perturbation: Perturbation::NormalMap(Arc::new(normal_map)),
+        }
+    }
+
+    /// Uses the luminance of `height` as a displacement along the normal, scaled by `strength`.
+    pub fn bump_map(
+        material: impl Material + Sync + Send + 'static,
+        height: impl Texture + Sync + Send + 'static,
+        strength: f64,
+    ) -> Self {
+        Self {
+            material: Box::new(material),
+            perturbation: Perturbation::BumpMap {
+                height: Arc::new(height),
+                strength,
+            },
+        }
+    }
+
+    /// Returns `rec` with its normal replaced by the shading normal.
+    fn shade<'a>(&self, rec: &HitRecord<'a>) -> HitRecord<'a> {
+        let n = rec.normal;
+        if rec.dpdu.near_zero() || rec.dpdv.near_zero() {
+            return *rec;
+        }
+
+        let shading_normal = match &self.perturbation {
+            Perturbation::NormalMap(normal_map) => {
+                let tangent = (rec.dpdu - rec.dpdu.dot(&n) * n).unit_vector();
+                let mut bitangent = n.cross(&tangent);
+                if bitangent.dot(&rec.dpdv) < 0.0 {
+                    bitangent = -bitangent;
+                }
+                let c = normal_map.hit_value(rec).0;
+                let (x, y, z) = (2.0 * c[0] - 1.0, 2.0 * c[1] - 1.0, 2.0 * c[2] - 1.0);
+                x * tangent + y * bitangent + z * n
+            }
+            Perturbation::BumpMap { height, strength } => {
+                // Finite differences over about the pixel footprint, or a small fixed step
+                // without differentials.
+                let step = |a: f64, b: f64| {
+                    let d = 0.5 * (f64::abs(a) + f64::abs(b));
+                    if d == 0.0 {
+                        0.0005
+                    } else {
+                        d
+                    }
+                };
+                let du = step(rec.dudx, rec.dudy);
+                let dv = step(rec.dvdx, rec.dvdy);
+                let displacement =
+                    |u: f64, v: f64, p: Point| strength * height.value(u, v, &p).luminance();
+
+                let h = displacement(rec.u, rec.v, rec.p);
+                let h_u = displacement(rec.u + du, rec.v, rec.p + du * rec.dpdu);
+                let h_v = displacement(rec.u, rec.v + dv, rec.p + dv * rec.dpdv);
+
+                let dpdu = rec.dpdu + ((h_u - h) / du) * n;
+                let dpdv = rec.dpdv + ((h_v - h) / dv) * n;
+                let bumped = dpdu.cross(&dpdv);
+                if bumped.dot(&n) < 0.0 {
+                    -bumped
+                } else {
+                    bumped
+                }
+            }
+        };
+        if shading_normal.near_zero() {
+            return *rec;
+        }
+
+        let mut shaded = *rec;
+        shaded.normal = valid_normal(shading_normal.unit_vector(), n, -rec.incoming.unit_vector());
+        shaded
+    }
+}
+
+/// Bends the shading normal `ns` so that the mirror reflection of the direction towards the
+/// viewer `wo` stays above the geometric surface with normal `ng`. Otherwise strongly perturbed
+/// normals send light into the surface and show up as black spots at grazing angles.
+fn valid_normal(ns: Vec3, ng: Vec3, wo: Vec3) -> Vec3 {
+    let reflected = 2.0 * wo.dot(&ns) * ns - wo;
+    let threshold = f64::min(0.9 * wo.dot(&ng), 0.01);
+    if reflected.dot(&ng) >= threshold {
+        return ns;
+    }
+
+    // Lift the reflection just above the surface and use the half vector towards it.
+    let lifted = (reflected + (threshold - reflected.dot(&ng)) * ng).unit_vector();
+    (wo + lifted).unit_vector()
+}
+
+impl Material for Bumped {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+        self.material.scatter(r_in, &self.shade(rec))
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.material.emitted(u, v, p)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        self.material.eval(r_in, &self.shade(rec), direction)
+    }
+
+    fn is_specular(&self, rec: &HitRecord) -> bool {
+        self.material.is_specular(rec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_normal() {
+        let ng = Vec3::new(0.0, 1.0, 0.0);
+        let wo = Vec3::new(1.0, 0.2, 0.0).unit_vector();
+
+        let flat = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(valid_normal(flat, ng, wo).dot(&flat), 1.0);
+
+        // Tilted away from the viewer far enough to reflect into the surface.
+        let tilted = Vec3::new(-1.0, 1.0, 0.0).unit_vector();
+        let ns = valid_normal(tilted, ng, wo);
+        let reflected = 2.0 * wo.dot(&ns) * ns - wo;
+        assert!(reflected.dot(&ng) > 0.0);
+        assert!(f64::abs(ns.length() - 1.0) < 1e-12);
+    }
+}
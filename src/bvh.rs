@@ -12,19 +12,35 @@ pub struct BVHNode {
 }
 
 impl BVHNode {
-    pub fn new(list: &HitList, start_time: f64, end_time: f64) -> Self {
-        Self::new_impl(&list.objects, 0, list.objects.len(), start_time, end_time)
+    /// Builds the same tree for the same state of `rng`, which picks the split axes.
+    pub fn with_rng<R: Rng + ?Sized>(
+        list: &HitList,
+        start_time: f64,
+        end_time: f64,
+        rng: &mut R,
+    ) -> Self {
+        Self::new_impl(
+            &list.objects,
+            0,
+            list.objects.len(),
+            start_time,
+            end_time,
+            rng,
+        )
     }
 
-    fn new_impl(
-        src_objects: &Vec<Arc<dyn Hit + Sync + Send>>,
+    fn new_impl<R: Rng + ?Sized>(
+        src_objects: &[Arc<dyn Hit + Sync + Send>],
         start: usize,
         end: usize,
         start_time: f64,
         end_time: f64,
+        rng: &mut R,
     ) -> Self {
-        let mut objects = src_objects.clone();
-        let mut rng = rand::thread_rng();
+        // Only copy the objects of this node, as copying them all at every node makes building
+        // quadratic in the number of objects.
+        let mut objects = src_objects[start..end].to_vec();
+        let (start, end) = (0, objects.len());
         let axis = rng.gen_range(0..=2);
         let comparator =
             |a: &Arc<dyn Hit + Sync + Send>, b: &Arc<dyn Hit + Sync + Send>| -> Ordering {
@@ -61,9 +77,11 @@ impl BVHNode {
                 objects[start..end].sort_by(comparator);
                 let mid = start + object_span / 2;
                 left = Arc::new(BVHNode::new_impl(
-                    &objects, start, mid, start_time, end_time,
+                    &objects, start, mid, start_time, end_time, rng,
+                ));
+                right = Arc::new(BVHNode::new_impl(
+                    &objects, mid, end, start_time, end_time, rng,
                 ));
-                right = Arc::new(BVHNode::new_impl(&objects, mid, end, start_time, end_time));
             }
         }
 
@@ -83,7 +101,7 @@ impl BVHNode {
 }
 
 impl Hit for BVHNode {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         if !self.bounding_box.hit(r, t_min, t_max) {
             return None;
         }
@@ -106,6 +124,18 @@ impl Hit for BVHNode {
         }
     }
 
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if !self.bounding_box.hit(r, t_min, t_max) {
+            return 1.0;
+        }
+
+        let left = self.left.transmittance(r, t_min, t_max);
+        if left == 0.0 {
+            return 0.0;
+        }
+        left * self.right.transmittance(r, t_min, t_max)
+    }
+
     fn bounding_box(&self, _: f64, _: f64) -> Option<AABB> {
         Some(self.bounding_box.clone())
     }
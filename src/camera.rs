@@ -1,5 +1,5 @@
 use crate::point::Point;
-use crate::ray::Ray;
+use crate::ray::{Ray, RayDifferential};
 use crate::vec3::Vec3;
 use rand::Rng;
 use std::f64::consts::PI;
@@ -11,13 +11,13 @@ pub struct Camera {
     vertical: Vec3,
     u: Vec3,
     v: Vec3,
-    w: Vec3,
     lens_radius: f64,
     start_time: f64,
     end_time: f64,
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         lookfrom: Point,
         lookat: Point,
@@ -52,21 +52,33 @@ impl Camera {
             vertical,
             u,
             v,
-            w,
             lens_radius,
             start_time,
             end_time,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
+    /// Returns a ray through the viewport position `(s, t)` that also tracks the rays through
+    /// the positions offset by `ds` and `dt`, usually the size of a pixel.
+    pub fn get_ray_differential(&self, s: f64, t: f64, ds: f64, dt: f64) -> Ray {
         let rd = self.lens_radius * Vec3::random_in_unit_disk().0;
         let offset = self.u * rd[0] + self.v * rd[1];
+        let origin = self.origin + offset;
 
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner - self.origin - offset + s * self.horizontal + t * self.vertical,
+        Ray::with_differential(
+            origin,
+            self.direction(s, t, offset),
             rand::thread_rng().gen_range(self.start_time..self.end_time),
+            RayDifferential {
+                rx_origin: origin,
+                rx_direction: self.direction(s + ds, t, offset),
+                ry_origin: origin,
+                ry_direction: self.direction(s, t + dt, offset),
+            },
         )
     }
+
+    fn direction(&self, s: f64, t: f64, offset: Vec3) -> Vec3 {
+        self.lower_left_corner - self.origin - offset + s * self.horizontal + t * self.vertical
+    }
 }
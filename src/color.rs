@@ -10,6 +10,10 @@ impl Color {
         Color(Vec3::new(r, g, b))
     }
 
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.0[0] + 0.7152 * self.0[1] + 0.0722 * self.0[2]
+    }
+
     pub fn to_rgb_array(self) -> [u8; 3] {
         [
             (256.0 * f64::clamp(f64::sqrt(self.0[0]), 0.0, 0.999)) as u8,
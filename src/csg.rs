@@ -0,0 +1,154 @@
+//! Constructive solid geometry: closed objects combined into new solids by walking the
+//! intervals where a ray is inside each of them.
+
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::ray::Ray;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// The first object with the second cut out of it.
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersection => in_a && in_b,
+            CsgOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// Combines two closed objects, whose surfaces each separate an inside from an outside. Every
+/// part of the surface keeps the material of the object it came from, so the walls of a hole
+/// cut by `Difference` show the material of the cutter.
+pub struct Csg {
+    a: Box<dyn Hit + Sync + Send>,
+    b: Box<dyn Hit + Sync + Send>,
+    op: CsgOp,
+}
+
+impl Csg {
+    pub fn new(
+        a: impl Hit + Sync + Send + 'static,
+        b: impl Hit + Sync + Send + 'static,
+        op: CsgOp,
+    ) -> Csg {
+        Csg {
+            a: Box::new(a),
+            b: Box::new(b),
+            op,
+        }
+    }
+}
+
+impl Hit for Csg {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hit_all(r, t_min, t_max).into_iter().next()
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
+        let a = self.a.bounding_box(start_time, end_time);
+        let b = self.b.bounding_box(start_time, end_time);
+        match self.op {
+            CsgOp::Union => Some(AABB::surrounding_box(&a?, &b?)),
+            CsgOp::Intersection => match (a, b) {
+                (Some(a), Some(b)) => Some(AABB::overlap(&a, &b)),
+                (a, b) => a.or(b),
+            },
+            CsgOp::Difference => a,
+        }
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        // Look past t_max: whether the ray starts inside an object is only known from the
+        // first surface it crosses, which may lie beyond the range.
+        let hits_a = self.a.hit_all(r, t_min, f64::INFINITY);
+        let hits_b = self.b.hit_all(r, t_min, f64::INFINITY);
+
+        // A ray that first leaves an object started inside it.
+        let mut in_a = hits_a.first().is_some_and(|rec| !rec.front_face);
+        let mut in_b = hits_b.first().is_some_and(|rec| !rec.front_face);
+        let mut inside = self.op.inside(in_a, in_b);
+
+        let mut events: Vec<(HitRecord, bool)> = hits_a
+            .into_iter()
+            .map(|rec| (rec, true))
+            .chain(hits_b.into_iter().map(|rec| (rec, false)))
+            .filter(|(rec, _)| rec.t <= t_max)
+            .collect();
+        events.sort_by(|(a, _), (b, _)| a.t.partial_cmp(&b.t).unwrap());
+
+        let mut hits = Vec::new();
+        for (mut rec, from_a) in events {
+            if from_a {
+                in_a = rec.front_face;
+            } else {
+                in_b = rec.front_face;
+            }
+            let now_inside = self.op.inside(in_a, in_b);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            // The surface of a cutter faces into the cutter, so flip its outward side. The
+            // stored normal already opposes the ray and stays as it is.
+            if self.op == CsgOp::Difference && !from_a {
+                rec.front_face = !rec.front_face;
+            }
+            hits.push(rec);
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+    use crate::point::Point;
+    use crate::sphere::Sphere;
+    use crate::vec3::Vec3;
+
+    fn sphere(x: f64) -> Sphere {
+        Sphere::new(
+            Point::new(x, 0.0, 0.0),
+            1.0,
+            Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+        )
+    }
+
+    #[test]
+    fn test_csg_intervals() {
+        let r = Ray::new(Point::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let ts = |csg: &Csg| -> Vec<f64> {
+            csg.hit_all(&r, 0.001, f64::INFINITY)
+                .iter()
+                .map(|rec| rec.t)
+                .collect()
+        };
+
+        // Spheres at -0.5 and 0.5 overlap between -0.5 and 0.5 on the x axis.
+        let union = Csg::new(sphere(-0.5), sphere(0.5), CsgOp::Union);
+        assert_eq!(ts(&union), vec![3.5, 6.5]);
+        let lens = Csg::new(sphere(-0.5), sphere(0.5), CsgOp::Intersection);
+        assert_eq!(ts(&lens), vec![4.5, 5.5]);
+        let bitten = Csg::new(sphere(-0.5), sphere(0.5), CsgOp::Difference);
+        assert_eq!(ts(&bitten), vec![3.5, 4.5]);
+
+        // The ray leaves the solid where it enters the cutter.
+        let hits = bitten.hit_all(&r, 0.001, f64::INFINITY);
+        assert!(hits[0].front_face && !hits[1].front_face);
+        assert_eq!(hits[1].normal[0], -1.0);
+
+        // Starting inside the union, the first surface is on the way out.
+        let rec = union.hit(&r, 5.0, f64::INFINITY).unwrap();
+        assert!(rec.t == 6.5 && !rec.front_face);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::material::Material;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// An axis-aligned box between two opposite corners, intersected analytically as the overlap
+/// of three slabs. Each face has its own outward normal and texture coordinates from 0 to 1
+/// across it. Rotate and move boxes with `RotateY` and `Translate`.
+pub struct Cuboid {
+    minimum: Point,
+    maximum: Point,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl Cuboid {
+    pub fn new(a: Point, b: Point, mat: impl Material + Sync + Send + 'static) -> Cuboid {
+        let corner = |f: fn(f64, f64) -> f64| {
+            Point::new(f(a.0[0], b.0[0]), f(a.0[1], b.0[1]), f(a.0[2], b.0[2]))
+        };
+        Cuboid {
+            minimum: corner(f64::min),
+            maximum: corner(f64::max),
+            mat: Box::new(mat),
+        }
+    }
+
+    /// The axes along the `u` and `v` texture coordinates of the faces perpendicular to `axis`.
+    fn face_axes(axis: usize) -> (usize, usize) {
+        match axis {
+            0 => (2, 1),
+            1 => (0, 2),
+            _ => (0, 1),
+        }
+    }
+}
+
+impl Hit for Cuboid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // The parameters and axes where the ray enters and leaves the box.
+        let (mut t_near, mut near_axis) = (f64::NEG_INFINITY, 0);
+        let (mut t_far, mut far_axis) = (f64::INFINITY, 0);
+        for axis in 0..3 {
+            let inv_d = 1.0 / r.direction()[axis];
+            let mut t0 = (self.minimum.0[axis] - r.origin().0[axis]) * inv_d;
+            let mut t1 = (self.maximum.0[axis] - r.origin().0[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > t_near {
+                (t_near, near_axis) = (t0, axis);
+            }
+            if t1 < t_far {
+                (t_far, far_axis) = (t1, axis);
+            }
+        }
+        if t_near > t_far {
+            return None;
+        }
+
+        let (t, axis) = if t_near >= t_min && t_near <= t_max {
+            (t_near, near_axis)
+        } else if t_far >= t_min && t_far <= t_max {
+            (t_far, far_axis)
+        } else {
+            return None;
+        };
+
+        let p = r.at(t);
+        let extent = self.maximum - self.minimum;
+        let center = 0.5 * (self.minimum.0 + self.maximum.0);
+        let mut outward_normal = Vec3::new(0.0, 0.0, 0.0);
+        outward_normal[axis] = if p.0[axis] > center[axis] { 1.0 } else { -1.0 };
+
+        let (a, b) = Cuboid::face_axes(axis);
+        let u = (p.0[a] - self.minimum.0[a]) / extent[a];
+        let v = (p.0[b] - self.minimum.0[b]) / extent[b];
+
+        let mut rec = HitRecord::new(r, outward_normal, t, u, v, self.mat.as_ref());
+        rec.dpdu[a] = extent[a];
+        rec.dpdv[b] = extent[b];
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AABB> {
+        Some(AABB::new(self.minimum, self.maximum))
+    }
+}
@@ -0,0 +1,123 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::ray::Ray;
+use crate::texture::Texture;
+use rand::Rng;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    /// Hits with an alpha below the threshold are skipped, giving hard edges.
+    Threshold(f64),
+    /// Hits are kept with a probability equal to their alpha, so partially transparent areas let
+    /// through that fraction of rays and converge to a soft blend.
+    Stochastic,
+}
+
+/// Cuts holes into an object where the luminance of an alpha texture is low, so leaves, fences
+/// and other detailed outlines can be rendered from simple quads. Rays pass through the holes,
+/// and so do shadow rays.
+pub struct Cutout {
+    object: Box<dyn Hit + Sync + Send>,
+    alpha: Box<dyn Texture + Sync + Send>,
+    mode: AlphaMode,
+}
+
+impl Cutout {
+    pub fn new(
+        object: impl Hit + Sync + Send + 'static,
+        alpha: impl Texture + Sync + Send + 'static,
+        mode: AlphaMode,
+    ) -> Self {
+        Self {
+            object: Box::new(object),
+            alpha: Box::new(alpha),
+            mode,
+        }
+    }
+}
+
+impl Hit for Cutout {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut rng = rand::thread_rng();
+        let mut t_min = t_min;
+        loop {
+            let rec = self.object.hit(r, t_min, t_max)?;
+            let alpha = self.alpha.hit_value(&rec).luminance();
+            let opaque = match self.mode {
+                AlphaMode::Threshold(threshold) => alpha >= threshold,
+                AlphaMode::Stochastic => alpha > rng.gen(),
+            };
+            if opaque {
+                return Some(rec);
+            }
+            // Look for the next surface of the object behind the hole, stepping a fixed distance
+            // whatever the length of the ray direction.
+            t_min = rec.t + 0.0001 / r.direction().length();
+        }
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
+        self.object.bounding_box(start_time, end_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::hit::HitList;
+    use crate::material::Lambertian;
+    use crate::point::Point;
+    use crate::quad::Quad;
+    use crate::texture::{CheckerSpace, CheckerTexture, SolidColor};
+    use crate::vec3::Vec3;
+
+    /// A square from (-1, -1) to (1, 1) at height `z`, starting at `x` in the x direction.
+    fn square(x: f64, z: f64) -> Quad {
+        Quad::new(
+            Point::new(x, -1.0, z),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+        )
+    }
+
+    #[test]
+    fn test_rays_pass_through_holes() {
+        // Opaque in the bottom left and top right quarters of the texture coordinates.
+        let alpha = || {
+            CheckerTexture::from_colors(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))
+                .with_space(CheckerSpace::Uv)
+                .with_frequency(2.0)
+        };
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        let single = Cutout::new(square(-1.0, 0.0), alpha(), AlphaMode::Threshold(0.5));
+        let solid = Ray::new(Point::new(-0.5, -0.5, 1.0), down, 0.0);
+        let hole = Ray::new(Point::new(0.5, -0.5, 1.0), down, 0.0);
+        assert!(single.hit(&solid, 0.001, f64::INFINITY).is_some());
+        assert!(single.hit(&hole, 0.001, f64::INFINITY).is_none());
+        assert_eq!(single.transmittance(&hole, 0.001, f64::INFINITY), 1.0);
+
+        // A surface just behind a hole is found, even along a long ray direction. The second
+        // square is shifted so that the ray hits it in an opaque quarter.
+        let mut layers = HitList::new();
+        layers.push(square(-1.0, 0.0));
+        layers.push(square(0.0, -0.01));
+        let layered = Cutout::new(layers, alpha(), AlphaMode::Threshold(0.5));
+        let long = Ray::new(Point::new(0.5, -0.5, 1.0), 1000.0 * down, 0.0);
+        let rec = layered.hit(&long, 0.0, f64::INFINITY).unwrap();
+        assert!(f64::abs(long.at(rec.t).0[2] + 0.01) < 1e-9);
+
+        // Half transparent surfaces let about half of the rays through.
+        let stochastic = Cutout::new(
+            square(-1.0, 0.0),
+            SolidColor::from_rgb(0.5, 0.5, 0.5),
+            AlphaMode::Stochastic,
+        );
+        let n = 10000;
+        let hits = (0..n)
+            .filter(|_| stochastic.hit(&solid, 0.001, f64::INFINITY).is_some())
+            .count();
+        assert!(f64::abs(hits as f64 / n as f64 - 0.5) < 0.03);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::color::Color;
+use crate::hit::HitRecord;
+use crate::material::HenyeyGreenstein;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use rand::Rng;
+
+/// Scene-wide fog whose density falls off exponentially with height above `base_height`:
+/// `density * exp(-height_falloff * (y - base_height))`. A falloff of zero gives homogeneous
+/// fog. Every ray travelling through the scene is attenuated by it, including the ones that
+/// escape to the background.
+pub struct Fog {
+    density: f64,
+    base_height: f64,
+    height_falloff: f64,
+    phase_function: HenyeyGreenstein,
+}
+
+impl Fog {
+    pub fn none() -> Fog {
+        Fog::homogeneous(0.0, Color::new(1.0, 1.0, 1.0), 0.0)
+    }
+
+    pub fn homogeneous(density: f64, albedo: Color, g: f64) -> Fog {
+        Fog::height(density, 0.0, 0.0, albedo, g)
+    }
+
+    pub fn height(
+        density: f64,
+        base_height: f64,
+        height_falloff: f64,
+        albedo: Color,
+        g: f64,
+    ) -> Fog {
+        Fog {
+            density,
+            base_height,
+            height_falloff,
+            phase_function: HenyeyGreenstein::from_color(albedo, g),
+        }
+    }
+
+    /// Returns the density at the ray origin and the rate at which it decays per unit of `t`.
+    fn density_along(&self, r: &Ray) -> (f64, f64) {
+        let origin_density =
+            self.density * f64::exp(-self.height_falloff * (r.origin().0[1] - self.base_height));
+        (origin_density, self.height_falloff * r.direction()[1])
+    }
+
+    /// Optical depth between the ray origin and `r.at(t)`.
+    fn optical_depth(&self, r: &Ray, t: f64) -> f64 {
+        let (origin_density, decay) = self.density_along(r);
+        let length = r.direction().length();
+        if f64::abs(decay) < 1e-9 {
+            origin_density * length * t
+        } else {
+            origin_density * length * (1.0 - f64::exp(-decay * t)) / decay
+        }
+    }
+
+    /// Fraction of light that survives between the ray origin and `r.at(t)`.
+    pub fn transmittance(&self, r: &Ray, t: f64) -> f64 {
+        if self.density <= 0.0 || t <= 0.0 {
+            return 1.0;
+        }
+        f64::exp(-self.optical_depth(r, t))
+    }
+
+    /// Samples the distance to the next scattering event along `r`, returning `None` if the ray
+    /// reaches `t_max` first. The optical depth is inverted analytically.
+    pub fn sample_distance(&self, r: &Ray, t_max: f64) -> Option<f64> {
+        if self.density <= 0.0 {
+            return None;
+        }
+
+        let target = -(1.0 - rand::thread_rng().gen::<f64>()).ln();
+        let (origin_density, decay) = self.density_along(r);
+        let scale = origin_density * r.direction().length();
+        let t = if f64::abs(decay) < 1e-9 {
+            target / scale
+        } else {
+            let remaining = 1.0 - target * decay / scale;
+            if remaining <= 0.0 {
+                // The total optical depth to infinity is smaller than the target.
+                return None;
+            }
+            -remaining.ln() / decay
+        };
+
+        if t < t_max {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// Builds the record of a scattering event at `r.at(t)`, so that the fog can be scattered
+    /// off like any other material.
+    pub fn scattering_record<'a>(&'a self, r: &Ray, t: f64) -> HitRecord<'a> {
+        // The normal is arbitrary inside a medium.
+        HitRecord::new(
+            r,
+            Vec3::new(1.0, 0.0, 0.0),
+            t,
+            0.0,
+            0.0,
+            &self.phase_function,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+
+    #[test]
+    fn test_sampled_distances_follow_transmittance() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let r = Ray::new(Point::new(0.0, 0.5, 0.0), Vec3::new(1.0, 0.6, 0.0), 0.0);
+        for fog in [
+            Fog::homogeneous(0.3, white, 0.0),
+            Fog::height(0.8, 0.0, 1.5, white, 0.0),
+        ] {
+            let n = 20000;
+            let distances: Vec<Option<f64>> = (0..n)
+                .map(|_| fog.sample_distance(&r, f64::INFINITY))
+                .collect();
+            for t in [0.5, 1.0, 2.0, 4.0] {
+                let scattered = distances
+                    .iter()
+                    .filter(|distance| distance.is_some_and(|d| d < t))
+                    .count();
+                let cdf = scattered as f64 / n as f64;
+                assert!(f64::abs(cdf - (1.0 - fog.transmittance(&r, t))) < 0.02);
+            }
+        }
+    }
+}
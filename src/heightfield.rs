@@ -0,0 +1,363 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::material::Material;
+use crate::mesh::intersect_triangle;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use image::error::{ImageError, ParameterError, ParameterErrorKind};
+use std::path::Path;
+
+/// A terrain given by a grid of `nx * nz` elevations, with x varying fastest, stretched over the
+/// box with corner `corner` and extent `size`. Elevations from 0 to 1 map onto the height of
+/// the box. Each grid cell is made of two triangles, but rays walk the cells they cross with a
+/// 2D DDA instead of going through a BVH of triangles, so even large grids are cheap to store
+/// and to hit. See Amanatides and Woo, "A Fast Voxel Traversal Algorithm for Ray Tracing"
+/// (1987).
+pub struct Heightfield {
+    nx: usize,
+    nz: usize,
+    heights: Vec<f64>,
+    normals: Vec<Vec3>,
+    corner: Point,
+    size: Vec3,
+    bounds: AABB,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl Heightfield {
+    pub fn new(
+        nx: usize,
+        nz: usize,
+        elevations: Vec<f64>,
+        corner: Point,
+        size: Vec3,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Self {
+        assert!(
+            nx >= 2 && nz >= 2,
+            "A heightfield needs at least 2x2 elevations."
+        );
+        assert_eq!(elevations.len(), nx * nz, "Heightfield size mismatch.");
+
+        let heights: Vec<f64> = elevations
+            .iter()
+            .map(|e| corner.0[1] + e * size[1])
+            .collect();
+        let (low, high) = heights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &h| {
+                (low.min(h), high.max(h))
+            });
+        let padding = 0.0001;
+        let bounds = AABB::new(
+            Point::new(corner.0[0], low - padding, corner.0[2]),
+            Point::new(corner.0[0] + size[0], high + padding, corner.0[2] + size[2]),
+        );
+
+        let mut heightfield = Self {
+            nx,
+            nz,
+            heights,
+            normals: Vec::new(),
+            corner,
+            size,
+            bounds,
+            mat: Box::new(mat),
+        };
+        heightfield.normals = (0..nz)
+            .flat_map(|j| (0..nx).map(move |i| (i, j)))
+            .map(|(i, j)| heightfield.vertex_normal(i, j))
+            .collect();
+        heightfield
+    }
+
+    /// Loads the elevations from the brightness of a grayscale image, with the top row of the
+    /// image at the far end of the z axis.
+    pub fn load(
+        path: impl AsRef<Path>,
+        corner: Point,
+        size: Vec3,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> image::ImageResult<Self> {
+        let img = image::open(path)?.to_luma16();
+        let (nx, nz) = (img.width() as usize, img.height() as usize);
+        if nx < 2 || nz < 2 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::Generic(
+                    "A heightfield needs at least 2x2 elevations.".to_string(),
+                ),
+            )));
+        }
+        let mut elevations = vec![0.0; nx * nz];
+        for (x, y, pixel) in img.enumerate_pixels() {
+            let j = nz - 1 - y as usize;
+            elevations[j * nx + x as usize] = pixel[0] as f64 / u16::MAX as f64;
+        }
+        Ok(Self::new(nx, nz, elevations, corner, size, mat))
+    }
+
+    /// Samples the elevations from the luminance of a texture, such as a `RampTexture` over noise,
+    /// looked up on the bottom of the box with texture coordinates from 0 to 1 along x and z.
+    pub fn from_texture(
+        texture: &dyn Texture,
+        nx: usize,
+        nz: usize,
+        corner: Point,
+        size: Vec3,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Self {
+        assert!(
+            nx >= 2 && nz >= 2,
+            "A heightfield needs at least 2x2 elevations."
+        );
+        let mut elevations = Vec::with_capacity(nx * nz);
+        for j in 0..nz {
+            for i in 0..nx {
+                let (u, v) = (i as f64 / (nx - 1) as f64, j as f64 / (nz - 1) as f64);
+                let p = corner + Vec3::new(u * size[0], 0.0, v * size[2]);
+                elevations.push(texture.value(u, v, &p).luminance());
+            }
+        }
+        Self::new(nx, nz, elevations, corner, size, mat)
+    }
+
+    fn cell_width(&self) -> f64 {
+        self.size[0] / (self.nx - 1) as f64
+    }
+
+    fn cell_depth(&self) -> f64 {
+        self.size[2] / (self.nz - 1) as f64
+    }
+
+    fn height(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.nx + i]
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point {
+        Point::new(
+            self.corner.0[0] + i as f64 * self.cell_width(),
+            self.height(i, j),
+            self.corner.0[2] + j as f64 * self.cell_depth(),
+        )
+    }
+
+    /// Estimates the normal at a grid point from the slopes to its neighbors.
+    fn vertex_normal(&self, i: usize, j: usize) -> Vec3 {
+        let (i0, i1) = (i.saturating_sub(1), usize::min(i + 1, self.nx - 1));
+        let (j0, j1) = (j.saturating_sub(1), usize::min(j + 1, self.nz - 1));
+        let dhdx =
+            (self.height(i1, j) - self.height(i0, j)) / ((i1 - i0) as f64 * self.cell_width());
+        let dhdz =
+            (self.height(i, j1) - self.height(i, j0)) / ((j1 - j0) as f64 * self.cell_depth());
+        Vec3::new(-dhdx, 1.0, -dhdz).unit_vector()
+    }
+
+    /// Returns the ray parameters where `r` enters and leaves the bounds.
+    fn clip(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (mut t0, mut t1) = (t_min, t_max);
+        for axis in 0..3 {
+            let inv_d = 1.0 / r.direction()[axis];
+            let mut near = (self.bounds.minimum.0[axis] - r.origin().0[axis]) * inv_d;
+            let mut far = (self.bounds.maximum.0[axis] - r.origin().0[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut near, &mut far);
+            }
+            // NaN from rays parallel to a slab on its boundary keeps the previous limits.
+            t0 = if near > t0 { near } else { t0 };
+            t1 = if far < t1 { far } else { t1 };
+            if t1 < t0 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+
+    /// Intersects the two triangles of cell `(i, j)`, between `t_min` and `t_max`.
+    fn hit_cell(
+        &self,
+        r: &Ray,
+        i: usize,
+        j: usize,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'_>> {
+        let (p00, p10) = (self.vertex(i, j), self.vertex(i + 1, j));
+        let (p01, p11) = (self.vertex(i, j + 1), self.vertex(i + 1, j + 1));
+
+        // A ray can cross both triangles, so keep the closer hit.
+        let (t, triangle) = [[p00, p11, p10], [p00, p01, p11]]
+            .into_iter()
+            .filter_map(|[a, b, c]| {
+                intersect_triangle(r, a, b, c, t_min, t_max).map(|(t, _, _)| (t, [a, b, c]))
+            })
+            .min_by(|(t1, _), (t2, _)| t1.partial_cmp(t2).unwrap())?;
+
+        let p = r.at(t);
+        let fx = ((p.0[0] - p00.0[0]) / self.cell_width()).clamp(0.0, 1.0);
+        let fz = ((p.0[2] - p00.0[2]) / self.cell_depth()).clamp(0.0, 1.0);
+        let u = (i as f64 + fx) / (self.nx - 1) as f64;
+        let v = (j as f64 + fz) / (self.nz - 1) as f64;
+
+        let [a, b, c] = triangle;
+        let geometric_normal = (b - a).cross(&(c - a)).unit_vector();
+        let mut rec = HitRecord::new(r, geometric_normal, t, u, v, self.mat.as_ref());
+
+        let normal = |i: usize, j: usize| self.normals[j * self.nx + i];
+        let shading_normal = ((1.0 - fx) * (1.0 - fz) * normal(i, j)
+            + fx * (1.0 - fz) * normal(i + 1, j)
+            + (1.0 - fx) * fz * normal(i, j + 1)
+            + fx * fz * normal(i + 1, j + 1))
+        .unit_vector();
+        rec.normal = if shading_normal.dot(&rec.normal) < 0.0 {
+            -shading_normal
+        } else {
+            shading_normal
+        };
+
+        // The surface is y = h(x, z), so the slopes follow from the normal.
+        rec.dpdu = Vec3::new(
+            self.size[0],
+            -shading_normal[0] / shading_normal[1] * self.size[0],
+            0.0,
+        );
+        rec.dpdv = Vec3::new(
+            0.0,
+            -shading_normal[2] / shading_normal[1] * self.size[2],
+            self.size[2],
+        );
+        Some(rec)
+    }
+}
+
+impl Hit for Heightfield {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.clip(r, t_min, t_max)?;
+
+        let cells = [self.nx - 1, self.nz - 1];
+        let cell_size = [self.cell_width(), self.cell_depth()];
+        let entry = r.at(t_enter);
+        let mut cell = [0; 2];
+        let mut step = [0i64; 2];
+        let mut t_next = [f64::INFINITY; 2];
+        let mut t_delta = [f64::INFINITY; 2];
+        for (k, axis) in [0, 2].into_iter().enumerate() {
+            let offset = (entry.0[axis] - self.corner.0[axis]) / cell_size[k];
+            cell[k] = (offset.floor().max(0.0) as usize).min(cells[k] - 1);
+
+            let d = r.direction()[axis];
+            if d > 0.0 {
+                step[k] = 1;
+                let boundary = self.corner.0[axis] + (cell[k] + 1) as f64 * cell_size[k];
+                t_next[k] = (boundary - r.origin().0[axis]) / d;
+                t_delta[k] = cell_size[k] / d;
+            } else if d < 0.0 {
+                step[k] = -1;
+                let boundary = self.corner.0[axis] + cell[k] as f64 * cell_size[k];
+                t_next[k] = (boundary - r.origin().0[axis]) / d;
+                t_delta[k] = -cell_size[k] / d;
+            }
+        }
+
+        let mut t = t_enter;
+        loop {
+            let k = if t_next[0] < t_next[1] { 0 } else { 1 };
+            let t_leave = f64::min(t_next[k], t_exit);
+
+            // Skip the triangles if the ray passes entirely above or below the cell.
+            let (y0, y1) = (r.at(t).0[1], r.at(t_leave).0[1]);
+            let (i, j) = (cell[0], cell[1]);
+            let corners = [
+                self.height(i, j),
+                self.height(i + 1, j),
+                self.height(i, j + 1),
+                self.height(i + 1, j + 1),
+            ];
+            let low = corners.iter().cloned().fold(f64::INFINITY, f64::min);
+            let high = corners.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            if f64::min(y0, y1) <= high && f64::max(y0, y1) >= low {
+                // Neighboring cells share edges, so let the triangles reach slightly past the
+                // cell rather than miss hits on the boundary.
+                let slack = 1e-6 * (t_leave - t).abs().max(1e-6);
+                if let Some(rec) = self.hit_cell(
+                    r,
+                    i,
+                    j,
+                    f64::max(t - slack, t_min),
+                    f64::min(t_leave + slack, t_max),
+                ) {
+                    return Some(rec);
+                }
+            }
+
+            if t_next[k] >= t_exit {
+                return None;
+            }
+            let next = cell[k] as i64 + step[k];
+            if next < 0 || next >= cells[k] as i64 {
+                return None;
+            }
+            cell[k] = next as usize;
+            t = t_next[k];
+            t_next[k] += t_delta[k];
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AABB> {
+        Some(self.bounds.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_hit_slope() {
+        // A ramp rising from y = 0 at x = 0 to y = 4 at x = 4.
+        let mut elevations = Vec::new();
+        for _ in 0..5 {
+            for i in 0..5 {
+                elevations.push(i as f64 / 4.0);
+            }
+        }
+        let ramp = Heightfield::new(
+            5,
+            5,
+            elevations,
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(4.0, 4.0, 4.0),
+            Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+        );
+
+        let down = Ray::new(Point::new(2.5, 10.0, 1.5), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = ramp.hit(&down, 0.0, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 7.5) < 1e-9);
+
+        // A ray crossing several cells along x before hitting the ramp at x = 3.
+        let across = Ray::new(Point::new(-1.0, 3.0, 2.2), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let rec = ramp.hit(&across, 0.0, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.p.0[0] - 3.0) < 1e-9);
+        assert!(rec.normal[0] < 0.0);
+
+        let above = Ray::new(Point::new(-1.0, 5.0, 2.2), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(ramp.hit(&above, 0.0, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_load_rejects_single_row() {
+        let path = std::env::temp_dir().join("raytracer_single_row_heightmap.png");
+        image::GrayImage::new(4, 1).save(&path).unwrap();
+        let heightfield = Heightfield::load(
+            &path,
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(heightfield, Err(ImageError::Parameter(_))));
+    }
+}
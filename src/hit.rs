@@ -6,18 +6,65 @@ use crate::ray::Ray;
 use crate::vec3::Vec3;
 
 pub trait Hit {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB>;
+
+    /// Returns every intersection of `r` within the range, ordered along the ray. By default
+    /// this asks `hit` for the next intersection a small distance past the last one until there
+    /// are no more, which works for any object. Objects that find all their intersections at
+    /// once can return them directly.
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        // The step is a distance, so it has to be scaled to the length of the direction.
+        let step = 0.0001 / r.direction().length();
+        let mut hits = Vec::new();
+        let mut t_min = t_min;
+        while let Some(rec) = self.hit(r, t_min, t_max) {
+            t_min = rec.t + step;
+            hits.push(rec);
+        }
+        hits
+    }
+
+    /// Returns the fraction of light that gets through the object between `t_min` and `t_max`
+    /// along the shadow ray `r`. Surfaces block all light; participating media let part of it
+    /// through.
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if self.hit(r, t_min, t_max).is_some() {
+            0.0
+        } else {
+            1.0
+        }
+    }
 }
 
+#[derive(Clone, Copy)]
 pub struct HitRecord<'a> {
     pub p: Point,
+    /// The hit position in the object's own space, before instancing transforms and motion move
+    /// the object into place, so textures looked up with it stick to the object.
+    pub object_p: Point,
     pub normal: Vec3,
     pub t: f64,
     pub u: f64,
     pub v: f64,
     pub front_face: bool,
     pub mat: &'a (dyn Material + Sync),
+    /// Direction of the ray that hit, not necessarily normalized.
+    pub incoming: Vec3,
+    /// Partial derivatives of the position with respect to the texture coordinates, zero for
+    /// objects without a parametrization.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Screen-space derivatives of the texture coordinates, set by `compute_differentials` for
+    /// rays carrying differentials and zero otherwise.
+    pub dudx: f64,
+    pub dvdx: f64,
+    pub dudy: f64,
+    pub dvdy: f64,
+    /// Screen-space derivatives of the position, set alongside the texture coordinate ones.
+    /// Unlike those they do not need a parametrization, so they are set on any object.
+    pub dpdx: Vec3,
+    pub dpdy: Vec3,
 }
 
 impl HitRecord<'_> {
@@ -33,6 +80,7 @@ impl HitRecord<'_> {
         let front_face = r.direction().dot(&outward_normal) < 0.0;
         HitRecord {
             p,
+            object_p: p,
             normal: if front_face {
                 outward_normal
             } else {
@@ -43,7 +91,66 @@ impl HitRecord<'_> {
             v,
             front_face,
             mat,
+            incoming: *r.direction(),
+            dpdu: Vec3::new(0.0, 0.0, 0.0),
+            dpdv: Vec3::new(0.0, 0.0, 0.0),
+            dudx: 0.0,
+            dvdx: 0.0,
+            dudy: 0.0,
+            dvdy: 0.0,
+            dpdx: Vec3::new(0.0, 0.0, 0.0),
+            dpdy: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Estimates how the texture coordinates change across a pixel by intersecting the offset
+    /// rays of `r` with the tangent plane at the hit.
+    pub fn compute_differentials(&mut self, r: &Ray) {
+        let differential = match r.differential() {
+            Some(differential) => differential,
+            None => return,
+        };
+
+        let n = self.normal;
+        let d = n.dot(&self.p.0);
+        let plane_offset = |origin: &Point, direction: &Vec3| -> Option<Vec3> {
+            let denom = n.dot(direction);
+            if f64::abs(denom) < 1e-12 {
+                return None;
+            }
+            let t = (d - n.dot(&origin.0)) / denom;
+            Some((*origin + t * *direction) - self.p)
+        };
+        let (dpdx, dpdy) = match (
+            plane_offset(&differential.rx_origin, &differential.rx_direction),
+            plane_offset(&differential.ry_origin, &differential.ry_direction),
+        ) {
+            (Some(dpdx), Some(dpdy)) => (dpdx, dpdy),
+            _ => return,
+        };
+        (self.dpdx, self.dpdy) = (dpdx, dpdy);
+
+        // Solve the overdetermined system dp = dpdu * du + dpdv * dv on the two axes that are
+        // the least parallel to the normal.
+        let (a, b) = if f64::abs(n[0]) > f64::abs(n[1]) && f64::abs(n[0]) > f64::abs(n[2]) {
+            (1, 2)
+        } else if f64::abs(n[1]) > f64::abs(n[2]) {
+            (0, 2)
+        } else {
+            (0, 1)
+        };
+        let det = self.dpdu[a] * self.dpdv[b] - self.dpdv[a] * self.dpdu[b];
+        if f64::abs(det) < 1e-12 {
+            return;
         }
+        let solve = |dp: Vec3| {
+            (
+                (self.dpdv[b] * dp[a] - self.dpdv[a] * dp[b]) / det,
+                (self.dpdu[a] * dp[b] - self.dpdu[b] * dp[a]) / det,
+            )
+        };
+        (self.dudx, self.dvdx) = solve(dpdx);
+        (self.dudy, self.dvdy) = solve(dpdy);
     }
 }
 
@@ -61,14 +168,10 @@ impl HitList {
     pub fn push(&mut self, object: impl Hit + Sync + Send + 'static) {
         self.objects.push(Arc::new(object));
     }
-
-    pub fn clear(&mut self) {
-        self.objects.clear();
-    }
 }
 
 impl Hit for HitList {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let mut res = None;
         let mut closest = t_max;
 
@@ -82,6 +185,17 @@ impl Hit for HitList {
         res
     }
 
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let mut res = 1.0;
+        for object in self.objects.iter() {
+            res *= object.transmittance(r, t_min, t_max);
+            if res == 0.0 {
+                break;
+            }
+        }
+        res
+    }
+
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
         let mut res = None;
 
@@ -0,0 +1,268 @@
+use crate::color::Color;
+use crate::hit::HitRecord;
+use crate::point::Point;
+use crate::texture::Texture;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How texture coordinates outside of `[0, 1]` are mapped back into the image.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    /// Maps the texel index `i` into `0..n`.
+    fn apply(self, i: i64, n: usize) -> usize {
+        let n = n as i64;
+        let i = match self {
+            WrapMode::Repeat => i.rem_euclid(n),
+            WrapMode::Clamp => i.clamp(0, n - 1),
+            WrapMode::Mirror => {
+                let i = i.rem_euclid(2 * n);
+                if i >= n {
+                    2 * n - 1 - i
+                } else {
+                    i
+                }
+            }
+        };
+        i as usize
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+    /// Bilinear lookups in the two mipmap levels closest to the size of the hit's footprint,
+    /// blended together. Falls back to bilinear filtering for hits without differentials.
+    Trilinear,
+}
+
+struct MipLevel {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+}
+
+impl MipLevel {
+    /// Halves the resolution by averaging blocks of 2x2 texels.
+    fn downsample(&self) -> MipLevel {
+        let width = usize::max(self.width / 2, 1);
+        let height = usize::max(self.height / 2, 1);
+        let mut texels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let texel = |dx: usize, dy: usize| {
+                    let sx = usize::min(2 * x + dx, self.width - 1);
+                    let sy = usize::min(2 * y + dy, self.height - 1);
+                    self.texels[sy * self.width + sx]
+                };
+                texels.push(0.25 * (texel(0, 0) + texel(1, 0) + texel(0, 1) + texel(1, 1)));
+            }
+        }
+        MipLevel {
+            width,
+            height,
+            texels,
+        }
+    }
+}
+
+/// A texture looked up from an image by the `u` and `v` texture coordinates of the hit, with
+/// `v = 0` at the bottom of the image. Texels are stored in linear color, along with a mipmap
+/// pyramid of successively halved resolutions.
+#[derive(Clone)]
+pub struct ImageTexture {
+    levels: Arc<Vec<MipLevel>>,
+    wrap_mode: WrapMode,
+    filter: Filter,
+}
+
+impl ImageTexture {
+    /// Loads a PNG, JPEG or any other image supported by the `image` crate, decoding its sRGB
+    /// texels to linear color.
+    pub fn load(path: impl AsRef<Path>) -> image::ImageResult<Self> {
+        Ok(Self::from_image(&image::open(path)?.to_rgb8(), true))
+    }
+
+    /// Set `srgb` if the image is sRGB encoded, like photographs and color maps usually are.
+    /// Data such as height maps is usually stored linearly.
+    pub fn from_image(img: &image::RgbImage, srgb: bool) -> Self {
+        let decode = |c: u8| {
+            let c = c as f64 / 255.0;
+            if srgb {
+                srgb_to_linear(c)
+            } else {
+                c
+            }
+        };
+        let texels = img
+            .pixels()
+            .map(|p| Color::new(decode(p[0]), decode(p[1]), decode(p[2])))
+            .collect();
+
+        Self::from_texels(img.width() as usize, img.height() as usize, texels)
+    }
+
+    /// Creates a texture from linear texels in rows from top to bottom.
+    pub fn from_texels(width: usize, height: usize, texels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0, "Image textures can't be empty.");
+        assert_eq!(texels.len(), width * height, "Image texture size mismatch.");
+        let mut levels = vec![MipLevel {
+            width,
+            height,
+            texels,
+        }];
+        while let Some(last) = levels.last().filter(|l| l.width > 1 || l.height > 1) {
+            levels.push(last.downsample());
+        }
+
+        Self {
+            levels: Arc::new(levels),
+            wrap_mode: WrapMode::Repeat,
+            filter: Filter::Trilinear,
+        }
+    }
+
+    pub fn with_wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    fn texel(&self, level: usize, x: i64, y: i64) -> Color {
+        let level = &self.levels[level];
+        let x = self.wrap_mode.apply(x, level.width);
+        let y = self.wrap_mode.apply(y, level.height);
+        level.texels[y * level.width + x]
+    }
+
+    fn nearest(&self, level: usize, u: f64, v: f64) -> Color {
+        let (width, height) = (self.levels[level].width, self.levels[level].height);
+        let x = u * width as f64;
+        let y = (1.0 - v) * height as f64;
+        self.texel(level, x.floor() as i64, y.floor() as i64)
+    }
+
+    fn bilinear(&self, level: usize, u: f64, v: f64) -> Color {
+        let (width, height) = (self.levels[level].width, self.levels[level].height);
+        // Texel centers are at half-integer coordinates.
+        let x = u * width as f64 - 0.5;
+        let y = (1.0 - v) * height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (dx, dy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        (1.0 - dx) * (1.0 - dy) * self.texel(level, x0, y0)
+            + dx * (1.0 - dy) * self.texel(level, x0 + 1, y0)
+            + (1.0 - dx) * dy * self.texel(level, x0, y0 + 1)
+            + dx * dy * self.texel(level, x0 + 1, y0 + 1)
+    }
+
+    /// Blends the two mip levels whose texel size is closest to `footprint`, the largest change
+    /// in texture coordinates across a pixel.
+    fn trilinear(&self, u: f64, v: f64, footprint: f64) -> Color {
+        let base = &self.levels[0];
+        let texels_across = footprint * usize::max(base.width, base.height) as f64;
+        let level = f64::max(texels_across, 1e-8).log2();
+        let last = (self.levels.len() - 1) as f64;
+
+        if level <= 0.0 {
+            return self.bilinear(0, u, v);
+        }
+        if level >= last {
+            return self.bilinear(self.levels.len() - 1, u, v);
+        }
+        let lower = level.floor();
+        let blend = level - lower;
+        (1.0 - blend) * self.bilinear(lower as usize, u, v)
+            + blend * self.bilinear(lower as usize + 1, u, v)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point) -> Color {
+        match self.filter {
+            Filter::Nearest => self.nearest(0, u, v),
+            Filter::Bilinear | Filter::Trilinear => self.bilinear(0, u, v),
+        }
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        if self.filter != Filter::Trilinear {
+            return self.value(rec.u, rec.v, &rec.p);
+        }
+
+        let footprint = [rec.dudx, rec.dvdx, rec.dudy, rec.dvdy]
+            .iter()
+            .fold(0.0, |acc: f64, d| acc.max(d.abs()));
+        self.trilinear(rec.u, rec.v, footprint)
+    }
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn test_mip_level_from_differentials() {
+        // A 2x2 checker, whose second and last level is a single gray texel.
+        let (black, white) = (Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let texture = ImageTexture::from_texels(2, 2, vec![white, black, black, white]);
+        let mat = Lambertian::from_color(white);
+        let r = Ray::new(Point::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        // A hit at the center of the top left texel.
+        let rec = HitRecord::new(&r, Vec3::new(0.0, 0.0, 1.0), 1.0, 0.25, 0.75, &mat);
+        let lookup = |texture: &ImageTexture, dudx: f64, dvdy: f64| {
+            texture.hit_value(&HitRecord { dudx, dvdy, ..rec }).0[0]
+        };
+
+        // Footprints up to a texel read the full resolution, one across the image reads the
+        // last level, and the levels in between are blended.
+        assert_eq!(lookup(&texture, 0.0, 0.0), 1.0);
+        assert_eq!(lookup(&texture, 0.5, 0.1), 1.0);
+        assert!(f64::abs(lookup(&texture, 0.1, -f64::sqrt(0.5)) - 0.75) < 1e-9);
+        assert_eq!(lookup(&texture, 1.0, 0.0), 0.5);
+        assert_eq!(lookup(&texture, 0.0, 8.0), 0.5);
+
+        // Only trilinear filtering looks at the footprint.
+        let bilinear = texture.with_filter(Filter::Bilinear);
+        assert_eq!(lookup(&bilinear, 8.0, 0.0), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be empty")]
+    fn test_empty_texture() {
+        ImageTexture::from_texels(0, 4, Vec::new());
+    }
+
+    #[test]
+    fn test_wrap_modes() {
+        assert_eq!(WrapMode::Repeat.apply(-1, 4), 3);
+        assert_eq!(WrapMode::Repeat.apply(5, 4), 1);
+        assert_eq!(WrapMode::Clamp.apply(-1, 4), 0);
+        assert_eq!(WrapMode::Clamp.apply(5, 4), 3);
+        assert_eq!(WrapMode::Mirror.apply(-1, 4), 0);
+        assert_eq!(WrapMode::Mirror.apply(5, 4), 2);
+        assert_eq!(WrapMode::Mirror.apply(8, 4), 0);
+    }
+}
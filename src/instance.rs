@@ -0,0 +1,209 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// Moves an object by `offset`, so one object can be modeled around the origin and placed
+/// anywhere.
+pub struct Translate {
+    object: Box<dyn Hit + Sync + Send>,
+    offset: Vec3,
+}
+
+impl Translate {
+    pub fn new(object: impl Hit + Sync + Send + 'static, offset: Vec3) -> Self {
+        Self {
+            object: Box::new(object),
+            offset,
+        }
+    }
+}
+
+impl Translate {
+    /// Moves `r` into the space of the object.
+    fn to_object(&self, r: &Ray) -> Ray {
+        Ray::new(*r.origin() - self.offset, *r.direction(), r.time())
+    }
+
+    /// Moves a hit on the object back out to world space.
+    fn to_world<'a>(&self, mut rec: HitRecord<'a>) -> HitRecord<'a> {
+        rec.p = rec.p + self.offset;
+        rec
+    }
+}
+
+impl Hit for Translate {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let rec = self.object.hit(&self.to_object(r), t_min, t_max)?;
+        Some(self.to_world(rec))
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        self.object
+            .hit_all(&self.to_object(r), t_min, t_max)
+            .into_iter()
+            .map(|rec| self.to_world(rec))
+            .collect()
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.object.transmittance(&self.to_object(r), t_min, t_max)
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
+        let bounds = self.object.bounding_box(start_time, end_time)?;
+        Some(AABB::new(
+            bounds.minimum + self.offset,
+            bounds.maximum + self.offset,
+        ))
+    }
+}
+
+/// Rotates an object by `angle` degrees around the y axis, counterclockwise when looking down
+/// the axis.
+pub struct RotateY {
+    object: Box<dyn Hit + Sync + Send>,
+    angle: f64,
+    bounds: Option<AABB>,
+}
+
+impl RotateY {
+    pub fn new(object: impl Hit + Sync + Send + 'static, angle: f64) -> Self {
+        let angle = angle.to_radians();
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+
+        // Rotating the corners of the object's box gives a box around the rotated object.
+        let bounds = object.bounding_box(0.0, 1.0).map(|b| {
+            let mut minimum = Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+            let mut maximum = -minimum;
+            for corner in 0..8 {
+                let pick = |axis: usize| {
+                    if corner & (1 << axis) == 0 {
+                        b.minimum.0[axis]
+                    } else {
+                        b.maximum.0[axis]
+                    }
+                };
+                let rotated = Vec3::new(pick(0), pick(1), pick(2)).rotate(&axis, angle);
+                for i in 0..3 {
+                    minimum[i] = minimum[i].min(rotated[i]);
+                    maximum[i] = maximum[i].max(rotated[i]);
+                }
+            }
+            AABB::new(Point(minimum), Point(maximum))
+        });
+
+        Self {
+            object: Box::new(object),
+            angle,
+            bounds,
+        }
+    }
+
+    fn rotate(&self, v: Vec3, angle: f64) -> Vec3 {
+        v.rotate(&Vec3::new(0.0, 1.0, 0.0), angle)
+    }
+
+    /// Rotates `r` into the space of the object.
+    fn to_object(&self, r: &Ray) -> Ray {
+        Ray::new(
+            Point(self.rotate(r.origin().0, -self.angle)),
+            self.rotate(*r.direction(), -self.angle),
+            r.time(),
+        )
+    }
+
+    /// Rotates a hit on the object back to world space.
+    fn to_world<'a>(&self, mut rec: HitRecord<'a>) -> HitRecord<'a> {
+        rec.p = Point(self.rotate(rec.p.0, self.angle));
+        rec.normal = self.rotate(rec.normal, self.angle);
+        rec.incoming = self.rotate(rec.incoming, self.angle);
+        rec.dpdu = self.rotate(rec.dpdu, self.angle);
+        rec.dpdv = self.rotate(rec.dpdv, self.angle);
+        rec
+    }
+}
+
+impl Hit for RotateY {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let rec = self.object.hit(&self.to_object(r), t_min, t_max)?;
+        Some(self.to_world(rec))
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        self.object
+            .hit_all(&self.to_object(r), t_min, t_max)
+            .into_iter()
+            .map(|rec| self.to_world(rec))
+            .collect()
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.object.transmittance(&self.to_object(r), t_min, t_max)
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AABB> {
+        self.bounds.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cuboid::Cuboid;
+    use crate::material::{HenyeyGreenstein, Lambertian};
+    use crate::sphere::Sphere;
+    use crate::volume::{DensityGrid, HeterogeneousMedium};
+
+    #[test]
+    fn test_rotated_translated_box() {
+        let unit = Cuboid::new(
+            Point::new(-1.0, -1.0, -1.0),
+            Point::new(1.0, 1.0, 1.0),
+            Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+        );
+        let instance = Translate::new(RotateY::new(unit, 45.0), Vec3::new(10.0, 0.0, 0.0));
+
+        // The rotated box reaches out to its diagonal along x.
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let rec = instance.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - (10.0 - f64::sqrt(2.0))) < 1e-9);
+        assert!(f64::abs(rec.p.0[0] - rec.t) < 1e-9);
+        assert!(rec.normal[0] < 0.0 && f64::abs(rec.normal.length() - 1.0) < 1e-9);
+        // The object space position stays on the unrotated box around the origin.
+        assert!(f64::abs(rec.object_p.0.length() - f64::sqrt(2.0)) < 1e-9);
+
+        let bounds = instance.bounding_box(0.0, 1.0).unwrap();
+        assert!(f64::abs(bounds.minimum.0[0] - (10.0 - f64::sqrt(2.0))) < 1e-9);
+    }
+
+    #[test]
+    fn test_instances_forward_all_hits_and_transmittance() {
+        let gray = || Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+        let ball = Sphere::new(Point::new(1.0, 0.0, 0.0), 1.0, gray());
+        let instance = Translate::new(RotateY::new(ball, 90.0), Vec3::new(0.0, 0.0, 5.0));
+
+        // The ball ends up centered on (0, 0, 4), so the ray goes in and out of it.
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), 0.0);
+        let hits = instance.hit_all(&r, 0.001, f64::INFINITY);
+        assert_eq!(hits.len(), 2);
+        assert!(f64::abs(hits[0].p.0[2] - 3.0) < 1e-9 && hits[0].front_face);
+        assert!(f64::abs(hits[1].p.0[2] - 5.0) < 1e-9 && !hits[1].front_face);
+
+        // A unit cube of smoke thickening along x, which ratio tracking lets partly through
+        // rather than all or nothing, once the shadow ray reaches it in object space.
+        let (minimum, maximum) = (Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 1.0));
+        let smoke = HeterogeneousMedium::new(
+            Cuboid::new(minimum, maximum, gray()),
+            DensityGrid::new(2, 1, 1, vec![0.0, 1.0], AABB::new(minimum, maximum)),
+            HenyeyGreenstein::from_color(Color::new(0.5, 0.5, 0.5), 0.0),
+        );
+        let instance = Translate::new(RotateY::new(smoke, 90.0), Vec3::new(0.0, 0.0, 5.0));
+        let r = Ray::new(Point::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!((0..100)
+            .map(|_| instance.transmittance(&r, 0.001, f64::INFINITY))
+            .any(|transmittance| transmittance > 0.0 && transmittance < 1.0));
+    }
+}
@@ -0,0 +1,326 @@
+use crate::aabb::AABB;
+use crate::color::Color;
+use crate::point::Point;
+use crate::vec3::Vec3;
+use rand::Rng;
+use std::f64::consts::PI;
+
+pub struct LightSample {
+    /// Unit direction from the shading point towards the light.
+    pub direction: Vec3,
+    /// Distance to the light along `direction`, infinite for distant lights.
+    pub distance: f64,
+    pub radiance: Color,
+    /// Solid angle density of `direction`. Delta lights report 1 and their irradiance as
+    /// `radiance`.
+    pub pdf: f64,
+}
+
+/// Conservative bounds of where a light is and in which directions it emits, used to estimate
+/// its contribution to a shading point before sampling it.
+#[derive(Clone)]
+pub struct LightBounds {
+    pub bounds: AABB,
+    /// Total emitted power, as luminance.
+    pub power: f64,
+    /// Emission happens within `theta_o` of `axis`, and then falls off to zero within a further
+    /// `theta_e`. Both angles are stored as cosines.
+    pub axis: Vec3,
+    pub cos_theta_o: f64,
+    pub cos_theta_e: f64,
+}
+
+impl LightBounds {
+    /// Bounds of a light emitting uniformly in every direction.
+    pub fn omnidirectional(bounds: AABB, power: f64) -> LightBounds {
+        LightBounds {
+            bounds,
+            power,
+            axis: Vec3::new(0.0, 0.0, 1.0),
+            cos_theta_o: -1.0,
+            cos_theta_e: 0.0,
+        }
+    }
+}
+
+/// A light source that can be sampled with shadow rays from a shading point.
+pub trait Light {
+    fn sample(&self, p: &Point) -> Option<LightSample>;
+    /// Returns `None` for lights infinitely far away.
+    fn bounds(&self) -> Option<LightBounds>;
+}
+
+/// A light infinitely far away subtending a cone of `angular_radius` radians, like the sun.
+/// With a zero angular radius it becomes a delta light and `radiance` is the irradiance it
+/// delivers to surfaces facing it.
+pub struct DirectionalLight {
+    direction: Vec3,
+    radiance: Color,
+    cos_max: f64,
+}
+
+impl DirectionalLight {
+    /// `direction` points from the scene towards the light.
+    pub fn new(direction: Vec3, radiance: Color, angular_radius: f64) -> Self {
+        Self {
+            direction: direction.unit_vector(),
+            radiance,
+            cos_max: f64::cos(angular_radius),
+        }
+    }
+
+    /// Returns the radiance seen when looking along the unit vector `direction`.
+    pub fn radiance_towards(&self, direction: &Vec3) -> Option<Color> {
+        if self.cos_max < 1.0 && direction.dot(&self.direction) >= self.cos_max {
+            Some(self.radiance)
+        } else {
+            None
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _: &Point) -> Option<LightSample> {
+        if self.cos_max >= 1.0 {
+            return Some(LightSample {
+                direction: self.direction,
+                distance: f64::INFINITY,
+                radiance: self.radiance,
+                pdf: 1.0,
+            });
+        }
+
+        let mut rng = rand::thread_rng();
+        let cos_theta = 1.0 - rng.gen::<f64>() * (1.0 - self.cos_max);
+        let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+        let phi = 2.0 * PI * rng.gen::<f64>();
+        let (t, b) = self.direction.orthonormal_basis();
+
+        Some(LightSample {
+            direction: sin_theta * f64::cos(phi) * t
+                + sin_theta * f64::sin(phi) * b
+                + cos_theta * self.direction,
+            distance: f64::INFINITY,
+            radiance: self.radiance,
+            pdf: 1.0 / (2.0 * PI * (1.0 - self.cos_max)),
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+/// An isotropic point light whose irradiance falls off with the inverse square of the distance.
+pub struct PointLight {
+    position: Point,
+    intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Point) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        let distance = f64::sqrt(distance_squared);
+
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: (1.0 / distance_squared) * self.intensity,
+            pdf: 1.0,
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds::omnidirectional(
+            AABB::new(self.position, self.position),
+            4.0 * PI * self.intensity.luminance(),
+        ))
+    }
+}
+
+/// A point light emitting in a cone around `direction`. The intensity is constant up to
+/// `falloff_start` degrees off the axis and smoothly falls to zero at `total_width` degrees.
+pub struct SpotLight {
+    position: Point,
+    direction: Vec3,
+    intensity: Color,
+    cos_falloff_start: f64,
+    cos_total_width: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point,
+        direction: Vec3,
+        intensity: Color,
+        total_width: f64,
+        falloff_start: f64,
+    ) -> Self {
+        Self {
+            position,
+            direction: direction.unit_vector(),
+            intensity,
+            cos_falloff_start: f64::cos(falloff_start.to_radians()),
+            cos_total_width: f64::cos(total_width.to_radians()),
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        let t = ((cos_theta - self.cos_total_width)
+            / (self.cos_falloff_start - self.cos_total_width))
+            .clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Point) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        let distance = f64::sqrt(distance_squared);
+        let direction = to_light / distance;
+
+        let falloff = self.falloff(-direction.dot(&self.direction));
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: (falloff / distance_squared) * self.intensity,
+            pdf: 1.0,
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let theta_o = f64::acos(self.cos_falloff_start);
+        let theta_e = f64::acos(self.cos_total_width) - theta_o;
+        Some(LightBounds {
+            bounds: AABB::new(self.position, self.position),
+            power: 4.0 * PI * self.intensity.luminance(),
+            axis: self.direction,
+            cos_theta_o: self.cos_falloff_start,
+            cos_theta_e: f64::cos(theta_e),
+        })
+    }
+}
+
+/// The light emitted by a sphere of constant `radiance`, typically one with a `DiffuseLight`
+/// material placed at the same position.
+pub struct SphereLight {
+    center: Point,
+    radius: f64,
+    radiance: Color,
+}
+
+impl SphereLight {
+    pub fn new(center: Point, radius: f64, radiance: Color) -> Self {
+        Self {
+            center,
+            radius,
+            radiance,
+        }
+    }
+}
+
+impl Light for SphereLight {
+    /// Samples the cone of directions subtended by the sphere uniformly.
+    fn sample(&self, p: &Point) -> Option<LightSample> {
+        let to_center = self.center - *p;
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+
+        let sin2_max = radius_squared / distance_squared;
+        let cos_max = f64::sqrt(1.0 - sin2_max);
+        // Equal to 1 - cos_max without the cancellation for small, distant spheres.
+        let one_minus_cos_max = sin2_max / (1.0 + cos_max);
+
+        let mut rng = rand::thread_rng();
+        let cos_theta = 1.0 - rng.gen::<f64>() * one_minus_cos_max;
+        let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+        let phi = 2.0 * PI * rng.gen::<f64>();
+        let axis = to_center / f64::sqrt(distance_squared);
+        let (t, b) = axis.orthonormal_basis();
+        let direction =
+            sin_theta * f64::cos(phi) * t + sin_theta * f64::sin(phi) * b + cos_theta * axis;
+
+        let half_b = direction.dot(&to_center);
+        let c = distance_squared - radius_squared;
+        let distance = half_b - f64::sqrt(f64::max(0.0, half_b * half_b - c));
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.radiance,
+            pdf: 1.0 / (2.0 * PI * one_minus_cos_max),
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        let area = 4.0 * PI * self.radius * self.radius;
+        Some(LightBounds::omnidirectional(
+            AABB::new(self.center - extent, self.center + extent),
+            PI * area * self.radiance.luminance(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_light_falls_off_with_distance() {
+        let light = PointLight::new(Point::new(0.0, 4.0, 0.0), Color::new(8.0, 8.0, 8.0));
+        let near = light.sample(&Point::new(0.0, 2.0, 0.0)).unwrap();
+        assert_eq!(near.distance, 2.0);
+        assert_eq!(near.radiance.0[0], 2.0);
+        assert_eq!(near.direction[1], 1.0);
+
+        let far = light.sample(&Point::new(0.0, 0.0, 0.0)).unwrap();
+        assert_eq!(far.radiance.0[0], 0.5);
+    }
+
+    #[test]
+    fn test_spot_light_cone() {
+        let light = SpotLight::new(
+            Point::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            45.0,
+            30.0,
+        );
+        let at_angle = |degrees: f64| {
+            let (sin, cos) = f64::sin_cos(degrees.to_radians());
+            light.sample(&Point::new(sin, 1.0 - cos, 0.0))
+        };
+
+        // Full intensity inside the inner cone, falling off to zero at its edge.
+        let inner = at_angle(20.0).unwrap();
+        assert!(f64::abs(inner.radiance.0[0] - 1.0) < 1e-9);
+        let falloff = at_angle(40.0).unwrap().radiance.0[0];
+        assert!(falloff > 0.0 && falloff < 1.0);
+        assert!(falloff < at_angle(35.0).unwrap().radiance.0[0]);
+
+        assert!(at_angle(50.0).is_none());
+        assert!(at_angle(120.0).is_none());
+    }
+}
@@ -0,0 +1,357 @@
+use crate::aabb::AABB;
+use crate::light::{Light, LightBounds};
+use crate::point::Point;
+use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LightSampling {
+    /// Lights are picked proportionally to their total power.
+    Power,
+    /// Lights are picked proportionally to their estimated contribution at the shading point by
+    /// walking down a BVH of the lights' spatial and directional bounds.
+    Bvh,
+}
+
+/// Picks one light to sample with a shadow ray from a shading point. Lights infinitely far away
+/// have no bounds, so they are picked uniformly, as often as the bounded lights together.
+pub struct LightSampler {
+    infinite_lights: Vec<Arc<dyn Light + Sync + Send>>,
+    bounded_lights: Vec<Arc<dyn Light + Sync + Send>>,
+    strategy: Strategy,
+}
+
+enum Strategy {
+    Power(Vec<f64>),
+    Bvh(Option<LightBVHNode>),
+}
+
+impl LightSampler {
+    pub fn new(lights: Vec<Arc<dyn Light + Sync + Send>>, sampling: LightSampling) -> Self {
+        let mut infinite_lights = Vec::new();
+        let mut bounded_lights = Vec::new();
+        let mut bounds = Vec::new();
+        for light in lights {
+            match light.bounds() {
+                Some(light_bounds) => {
+                    bounded_lights.push(light);
+                    bounds.push(light_bounds);
+                }
+                None => infinite_lights.push(light),
+            }
+        }
+
+        let strategy = match sampling {
+            LightSampling::Power => {
+                let mut cdf = Vec::with_capacity(bounds.len());
+                let mut total = 0.0;
+                for light_bounds in &bounds {
+                    total += light_bounds.power;
+                    cdf.push(total);
+                }
+                Strategy::Power(cdf)
+            }
+            LightSampling::Bvh => {
+                let mut leaves: Vec<(usize, LightBounds)> = bounds
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, light_bounds)| light_bounds.power > 0.0)
+                    .collect();
+                Strategy::Bvh(if leaves.is_empty() {
+                    None
+                } else {
+                    Some(LightBVHNode::new(&mut leaves))
+                })
+            }
+        };
+
+        Self {
+            infinite_lights,
+            bounded_lights,
+            strategy,
+        }
+    }
+
+    /// Returns a light along with the probability it was picked with.
+    pub fn sample(&self, p: &Point) -> Option<(&(dyn Light + Sync + Send), f64)> {
+        if self.infinite_lights.is_empty() && self.bounded_lights.is_empty() {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        let bounded = usize::from(!self.bounded_lights.is_empty());
+        let infinite_probability =
+            self.infinite_lights.len() as f64 / (self.infinite_lights.len() + bounded) as f64;
+
+        let u: f64 = rng.gen();
+        if u < infinite_probability {
+            let index = usize::min(
+                (u / infinite_probability * self.infinite_lights.len() as f64) as usize,
+                self.infinite_lights.len() - 1,
+            );
+            let pmf = infinite_probability / self.infinite_lights.len() as f64;
+            return Some((self.infinite_lights[index].as_ref(), pmf));
+        }
+
+        let (index, pmf) = match &self.strategy {
+            Strategy::Power(cdf) => {
+                let total = *cdf.last()?;
+                if total <= 0.0 {
+                    return None;
+                }
+                let target = rng.gen::<f64>() * total;
+                let index = usize::min(cdf.partition_point(|&c| c <= target), cdf.len() - 1);
+                let previous = if index == 0 { 0.0 } else { cdf[index - 1] };
+                (index, (cdf[index] - previous) / total)
+            }
+            Strategy::Bvh(root) => root.as_ref()?.sample(p, &mut rng)?,
+        };
+
+        Some((
+            self.bounded_lights[index].as_ref(),
+            (1.0 - infinite_probability) * pmf,
+        ))
+    }
+}
+
+enum LightBVHNode {
+    Leaf {
+        light: usize,
+        bounds: LightBounds,
+    },
+    Interior {
+        bounds: LightBounds,
+        children: Box<[LightBVHNode; 2]>,
+    },
+}
+
+impl LightBVHNode {
+    /// Builds the tree by recursively splitting the lights at the median of their centers along
+    /// the widest axis.
+    fn new(leaves: &mut [(usize, LightBounds)]) -> Self {
+        if let [(light, bounds)] = leaves {
+            return LightBVHNode::Leaf {
+                light: *light,
+                bounds: bounds.clone(),
+            };
+        }
+
+        let centers = leaves
+            .iter()
+            .map(|(_, light_bounds)| center(&light_bounds.bounds))
+            .fold(None, |acc: Option<AABB>, c| {
+                let point_box = AABB::new(c, c);
+                Some(match acc {
+                    None => point_box,
+                    Some(acc) => AABB::surrounding_box(&acc, &point_box),
+                })
+            })
+            .unwrap();
+        let extent = centers.maximum - centers.minimum;
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].partial_cmp(&extent[b]).unwrap())
+            .unwrap();
+
+        leaves.sort_by(|(_, a), (_, b)| {
+            center(&a.bounds).0[axis]
+                .partial_cmp(&center(&b.bounds).0[axis])
+                .unwrap()
+        });
+        let (left, right) = leaves.split_at_mut(leaves.len() / 2);
+        let children = Box::new([LightBVHNode::new(left), LightBVHNode::new(right)]);
+        LightBVHNode::Interior {
+            bounds: union(children[0].bounds(), children[1].bounds()),
+            children,
+        }
+    }
+
+    fn bounds(&self) -> &LightBounds {
+        match self {
+            LightBVHNode::Leaf { bounds, .. } | LightBVHNode::Interior { bounds, .. } => bounds,
+        }
+    }
+
+    /// Walks down the tree, choosing children proportionally to their importance at `p`.
+    fn sample(&self, p: &Point, rng: &mut impl Rng) -> Option<(usize, f64)> {
+        let mut node = self;
+        let mut pmf = 1.0;
+        loop {
+            match node {
+                LightBVHNode::Leaf { light, bounds } => {
+                    return if importance(bounds, p) > 0.0 {
+                        Some((*light, pmf))
+                    } else {
+                        None
+                    };
+                }
+                LightBVHNode::Interior { children, .. } => {
+                    let left = importance(children[0].bounds(), p);
+                    let right = importance(children[1].bounds(), p);
+                    if left == 0.0 && right == 0.0 {
+                        return None;
+                    }
+                    let left_probability = left / (left + right);
+                    if rng.gen::<f64>() < left_probability {
+                        pmf *= left_probability;
+                        node = &children[0];
+                    } else {
+                        pmf *= 1.0 - left_probability;
+                        node = &children[1];
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn center(bounds: &AABB) -> Point {
+    Point(0.5 * (bounds.minimum.0 + bounds.maximum.0))
+}
+
+/// Returns the cosine of `max(0, a - b)` for angles given by their sines and cosines.
+fn cos_sub_clamped(sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64) -> f64 {
+    if cos_a > cos_b {
+        1.0
+    } else {
+        cos_a * cos_b + sin_a * sin_b
+    }
+}
+
+/// Returns the sine of `max(0, a - b)` for angles given by their sines and cosines.
+fn sin_sub_clamped(sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64) -> f64 {
+    if cos_a > cos_b {
+        0.0
+    } else {
+        sin_a * cos_b - cos_a * sin_b
+    }
+}
+
+fn sin_from_cos(cos: f64) -> f64 {
+    f64::sqrt(f64::max(0.0, 1.0 - cos * cos))
+}
+
+/// Conservative estimate of the contribution of the lights in `light_bounds` to `p`, following
+/// Conty Estevez and Kulla, "Importance Sampling of Many Lights with Adaptive Tree Splitting"
+/// (2018).
+fn importance(light_bounds: &LightBounds, p: &Point) -> f64 {
+    let bounds = &light_bounds.bounds;
+    let pc = center(bounds);
+    let radius = 0.5 * (bounds.maximum - bounds.minimum).length();
+    // Clamped away from zero for shading points right at a point light.
+    let distance_squared = f64::max(f64::max((*p - pc).length_squared(), radius), 1e-8);
+
+    // Angle between the emission axis and the direction towards p.
+    let to_p = *p - pc;
+    let cos_theta_w = if to_p.near_zero() {
+        1.0
+    } else {
+        light_bounds.axis.dot(&to_p.unit_vector())
+    };
+    let sin_theta_w = sin_from_cos(cos_theta_w);
+
+    // Angle subtended by the bounds as seen from p.
+    let cos_theta_b = if (*p - pc).length_squared() <= radius * radius {
+        -1.0
+    } else {
+        sin_from_cos(f64::sqrt(radius * radius / (*p - pc).length_squared()))
+    };
+    let sin_theta_b = sin_from_cos(cos_theta_b);
+
+    let cos_theta_o = light_bounds.cos_theta_o;
+    let sin_theta_o = sin_from_cos(cos_theta_o);
+    let cos_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, cos_theta_o);
+    let sin_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, cos_theta_o);
+    let cos_theta_p = cos_sub_clamped(sin_x, cos_x, sin_theta_b, cos_theta_b);
+    if cos_theta_p <= light_bounds.cos_theta_e {
+        return 0.0;
+    }
+
+    light_bounds.power * cos_theta_p / distance_squared
+}
+
+fn union(a: &LightBounds, b: &LightBounds) -> LightBounds {
+    let theta_a = f64::acos(a.cos_theta_o.clamp(-1.0, 1.0));
+    let theta_b = f64::acos(b.cos_theta_o.clamp(-1.0, 1.0));
+    let theta_d = f64::acos(a.axis.dot(&b.axis).clamp(-1.0, 1.0));
+
+    let (axis, cos_theta_o) = if f64::min(theta_d + theta_b, PI) <= theta_a {
+        (a.axis, a.cos_theta_o)
+    } else if f64::min(theta_d + theta_a, PI) <= theta_b {
+        (b.axis, b.cos_theta_o)
+    } else {
+        let theta_o = 0.5 * (theta_a + theta_d + theta_b);
+        let rotation_axis = a.axis.cross(&b.axis);
+        if theta_o >= PI || rotation_axis.near_zero() {
+            (a.axis, -1.0)
+        } else {
+            let axis = a
+                .axis
+                .rotate(&rotation_axis.unit_vector(), theta_o - theta_a);
+            (axis, f64::cos(theta_o))
+        }
+    };
+
+    LightBounds {
+        bounds: AABB::surrounding_box(&a.bounds, &b.bounds),
+        power: a.power + b.power,
+        axis,
+        cos_theta_o,
+        cos_theta_e: f64::min(a.cos_theta_e, b.cos_theta_e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::light::{PointLight, SpotLight};
+    use crate::vec3::Vec3;
+
+    /// Samples `sampler` many times and returns the pmf each of `lights` was picked with, or
+    /// zero for lights that were never picked.
+    fn picked_pmfs(
+        sampler: &LightSampler,
+        lights: &[Arc<dyn Light + Sync + Send>],
+        p: &Point,
+    ) -> Vec<f64> {
+        let mut pmfs = vec![0.0; lights.len()];
+        for _ in 0..10000 {
+            let (light, pmf) = sampler.sample(p).unwrap();
+            let index = lights
+                .iter()
+                .position(|l| std::ptr::addr_eq(l.as_ref(), light))
+                .unwrap();
+            pmfs[index] = pmf;
+        }
+        pmfs
+    }
+
+    #[test]
+    fn test_sampled_pmfs_sum_to_one() {
+        let white = |intensity: f64| Color::new(intensity, intensity, intensity);
+        let lights: Vec<Arc<dyn Light + Sync + Send>> = vec![
+            Arc::new(PointLight::new(Point::new(0.0, 4.0, 0.0), white(10.0))),
+            Arc::new(PointLight::new(Point::new(3.0, 1.0, -2.0), white(2.0))),
+            Arc::new(PointLight::new(Point::new(-3.0, 1.0, 2.0), white(0.0))),
+            Arc::new(SpotLight::new(
+                Point::new(1.0, 3.0, 1.0),
+                Vec3::new(0.0, -1.0, 0.0),
+                white(5.0),
+                60.0,
+                40.0,
+            )),
+        ];
+
+        for sampling in [LightSampling::Power, LightSampling::Bvh] {
+            let sampler = LightSampler::new(lights.clone(), sampling);
+            let pmfs = picked_pmfs(&sampler, &lights, &Point::new(0.5, 0.0, 0.5));
+            assert!(f64::abs(pmfs.iter().sum::<f64>() - 1.0) < 1e-9);
+            assert_eq!(pmfs[2], 0.0);
+
+            // Right at a point light, where the distance to it vanishes.
+            let (_, pmf) = sampler.sample(&Point::new(3.0, 1.0, -2.0)).unwrap();
+            assert!(pmf > 0.0 && pmf <= 1.0);
+        }
+    }
+}
@@ -1,47 +1,169 @@
+use crate::aabb::AABB;
+use crate::bump::Bumped;
 use crate::bvh::BVHNode;
 use crate::camera::Camera;
 use crate::color::Color;
-use crate::hit::{Hit, HitList};
-use crate::material::{Dielectric, Lambertian, Metal};
+use crate::csg::{Csg, CsgOp};
+use crate::cuboid::Cuboid;
+use crate::cutout::{AlphaMode, Cutout};
+use crate::fog::Fog;
+use crate::heightfield::Heightfield;
+use crate::hit::{Hit, HitList, HitRecord};
+use crate::image_texture::{Filter, ImageTexture, WrapMode};
+use crate::instance::{RotateY, Translate};
+use crate::light::{DirectionalLight, Light, PointLight, SphereLight, SpotLight};
+use crate::light_sampler::{LightSampler, LightSampling};
+use crate::material::{
+    Dielectric, DiffuseLight, HenyeyGreenstein, Lambertian, Metal, MetallicRoughness,
+};
+use crate::mesh::Mesh;
+use crate::noise::{Fbm, RidgedMultifractal};
+use crate::perlin::Perlin;
 use crate::point::Point;
+use crate::procedural::{BrickTexture, MarbleTexture, WoodTexture};
+use crate::quad::Quad;
+use crate::quadric::{Cone, Cylinder, Disk};
+use crate::ramp::{ColorRamp, RampInterpolation};
 use crate::ray::Ray;
+use crate::sdf::{Capsule, SdfBox, SdfCombine, SdfFn, SdfObject, SdfOp, SdfSphere, SdfTorus};
+use crate::sky::{PreethamSky, Sky};
 use crate::sphere::Sphere;
-use crate::texture::{CheckerTexture, NoiseTexture};
+use crate::texture::{
+    CheckerSpace, CheckerTexture, NoiseMode, NoiseTexture, RampInput, RampTexture, SolidColor,
+    TransformTexture, TriplanarTexture,
+};
+use crate::texture_ops::{
+    Channel, ChannelTexture, ClampTexture, CombineTexture, InvertTexture, MathOp, MathTexture,
+    MixTexture,
+};
+use crate::torus::Torus;
 use crate::vec3::Vec3;
-use rand::Rng;
+use crate::volume::{DensityGrid, HeterogeneousMedium, PerlinDensity};
+use crate::worley::{Worley, WorleyMode};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use rayon::prelude::*;
+use std::sync::Arc;
 
 mod aabb;
+mod bump;
 mod bvh;
 mod camera;
 mod color;
+mod csg;
+mod cuboid;
+mod cutout;
+mod fog;
+mod heightfield;
 mod hit;
+mod image_texture;
+mod instance;
+mod light;
+mod light_sampler;
 mod material;
+mod mesh;
+mod noise;
 mod perlin;
 mod point;
+mod procedural;
+mod quad;
+mod quadric;
+mod quartic;
+mod ramp;
 mod ray;
+mod sdf;
+mod sky;
 mod sphere;
 mod texture;
+mod texture_ops;
+mod torus;
 mod vec3;
+mod volume;
+mod worley;
 
-fn ray_color<T: Hit>(r: &Ray, world: &T, depth: i32) -> Color {
+/// Returns the radiance arriving along `r`. Light sources hit directly are only counted if
+/// `count_lights` is set, as vertices that sample lights with shadow rays already accounted for
+/// them.
+fn ray_color<T: Hit>(
+    r: &Ray,
+    world: &T,
+    lights: &LightSampler,
+    sky: &Sky,
+    fog: &Fog,
+    depth: i32,
+    count_lights: bool,
+) -> Color {
     if depth <= 0 {
         return Color::new(0.0, 0.0, 0.0);
     }
 
-    if let Some(rec) = world.hit(r, 0.001, f64::INFINITY) {
-        if let Some((scattered, attenuation)) = rec.mat.scatter(r, &rec) {
-            return Color(attenuation.0 * ray_color(&scattered, world, depth - 1).0);
+    let hit = world.hit(r, 0.001, f64::INFINITY);
+
+    let t_max = hit.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+    let rec = match (fog.sample_distance(r, t_max), hit) {
+        (Some(t), _) => fog.scattering_record(r, t),
+        (None, Some(mut rec)) => {
+            rec.compute_differentials(r);
+            rec
         }
-        return Color::new(0.0, 0.0, 0.0);
+        (None, None) => return sky.value(&r.direction().unit_vector(), count_lights),
+    };
+
+    let mut color = if count_lights {
+        rec.mat.emitted(rec.u, rec.v, &rec.p)
+    } else {
+        Color::new(0.0, 0.0, 0.0)
+    };
+
+    let specular = rec.mat.is_specular(&rec);
+    if !specular {
+        color += direct_light(r, &rec, world, lights, fog);
+    }
+
+    if let Some((scattered, attenuation)) = rec.mat.scatter(r, &rec) {
+        let incoming = ray_color(&scattered, world, lights, sky, fog, depth - 1, specular);
+        color += Color(attenuation.0 * incoming.0);
+    }
+    color
+}
+
+/// Estimates the light arriving at `rec` directly from light sources by sampling one of them
+/// with a shadow ray.
+fn direct_light<T: Hit>(
+    r: &Ray,
+    rec: &HitRecord,
+    world: &T,
+    lights: &LightSampler,
+    fog: &Fog,
+) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+    let (light, pmf) = match lights.sample(&rec.p) {
+        Some(picked) => picked,
+        None => return black,
+    };
+    let sample = match light.sample(&rec.p) {
+        Some(sample) => sample,
+        None => return black,
+    };
+
+    let f = rec.mat.eval(r, rec, &sample.direction);
+    if f.0.near_zero() {
+        return black;
     }
 
-    let unit_direction = r.direction().unit_vector();
-    let t = 0.5 * (unit_direction[1] + 1.0);
-    (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+    // Stop short of the light itself, in case it is also part of the world.
+    let shadow_ray = Ray::new(rec.p, sample.direction, r.time());
+    let transmittance = world.transmittance(&shadow_ray, 0.001, sample.distance - 0.001);
+    if transmittance == 0.0 {
+        return black;
+    }
+
+    let transmittance = transmittance * fog.transmittance(&shadow_ray, sample.distance);
+    (transmittance / (sample.pdf * pmf)) * Color(f.0 * sample.radiance.0)
 }
 
-fn random_scene() -> BVHNode {
+/// Generates the same scene for the same `seed`.
+fn random_scene(seed: u64) -> BVHNode {
     let mut world = HitList::new();
 
     let ground_material = Lambertian::new(CheckerTexture::from_colors(
@@ -54,7 +176,7 @@ fn random_scene() -> BVHNode {
         ground_material,
     ));
 
-    let mut rng = rand::thread_rng();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
 
     for a in -11..11 {
         for b in -11..11 {
@@ -105,11 +227,66 @@ fn random_scene() -> BVHNode {
     let material3 = Metal::new(Color::new(0.7, 0.6, 0.5), 0.0);
     world.push(Sphere::new(Point::new(4.0, 1.0, 0.0), 1.0, material3));
 
-    BVHNode::new(&world, 0.0, 1.0)
+    BVHNode::with_rng(&world, 0.0, 1.0, &mut rng)
+}
+
+/// A variant of `random_scene` at night, where many of the small spheres glow.
+fn glowing_spheres(seed: u64) -> (BVHNode, Vec<Arc<dyn Light + Sync + Send>>) {
+    let mut world = HitList::new();
+    let mut lights: Vec<Arc<dyn Light + Sync + Send>> = Vec::new();
+
+    world.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+    ));
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let center = Point::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+            if (center - Point::new(4.0, 0.2, 0.0)).length() <= 0.9 {
+                continue;
+            }
+
+            let color = Color::new(rng.gen(), rng.gen(), rng.gen());
+            if rng.gen::<f64>() < 0.5 {
+                let radiance = rng.gen_range(1.0..8.0) * color;
+                world.push(Sphere::new(center, 0.2, DiffuseLight::from_color(radiance)));
+                lights.push(Arc::new(SphereLight::new(center, 0.2, radiance)));
+            } else {
+                world.push(Sphere::new(center, 0.2, Lambertian::from_color(color)));
+            }
+        }
+    }
+
+    world.push(Sphere::new(
+        Point::new(0.0, 1.0, 0.0),
+        1.0,
+        Dielectric::new(1.5),
+    ));
+    world.push(Sphere::new(
+        Point::new(-4.0, 1.0, 0.0),
+        1.0,
+        Lambertian::from_color(Color::new(0.4, 0.2, 0.1)),
+    ));
+    world.push(Sphere::new(
+        Point::new(4.0, 1.0, 0.0),
+        1.0,
+        Metal::new(Color::new(0.7, 0.6, 0.5), 0.0),
+    ));
+
+    (BVHNode::with_rng(&world, 0.0, 1.0, &mut rng), lights)
 }
 
-fn two_spheres() -> BVHNode {
+fn two_spheres(seed: u64) -> BVHNode {
     let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
 
     let checker = CheckerTexture::from_colors(Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
 
@@ -124,13 +301,15 @@ fn two_spheres() -> BVHNode {
         Lambertian::new(checker),
     ));
 
-    BVHNode::new(&objects, 0.0, 1.0)
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
 }
 
-fn two_perlin_spheres() -> BVHNode {
+fn two_perlin_spheres(seed: u64) -> BVHNode {
     let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
 
-    let perlin_texture = NoiseTexture::new();
+    let perlin_texture =
+        NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 4.0, NoiseMode::Marble);
     objects.push(Sphere::new(
         Point::new(0.0, -1000.0, 0.0),
         1000.0,
@@ -142,7 +321,1063 @@ fn two_perlin_spheres() -> BVHNode {
         Lambertian::new(perlin_texture),
     ));
 
-    BVHNode::new(&objects, 0.0, 1.0)
+    // The plain noise and the turbulence the marble veins are made of.
+    objects.push(Sphere::new(
+        Point::new(2.0, 0.8, -2.8),
+        0.8,
+        Lambertian::new(NoiseTexture::with_noise(
+            Perlin::with_rng(&mut rng),
+            4.0,
+            NoiseMode::Plain,
+        )),
+    ));
+    objects.push(Sphere::new(
+        Point::new(2.0, 0.8, 2.8),
+        0.8,
+        Lambertian::new(NoiseTexture::with_noise(
+            Perlin::with_rng(&mut rng),
+            4.0,
+            NoiseMode::Turbulence,
+        )),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn perlin_smoke(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    // A density grid from smoke.raw in the working directory if there is one, stretched over the
+    // box around the sphere, and Perlin turbulence otherwise.
+    let boundary = Sphere::new(
+        Point::new(0.0, 2.0, 0.0),
+        2.0,
+        Lambertian::from_color(Color::new(0.0, 0.0, 0.0)),
+    );
+    let phase_function = HenyeyGreenstein::from_color(Color::new(0.9, 0.9, 0.9), 0.3);
+    let bounds = AABB::new(Point::new(-2.0, 0.0, -2.0), Point::new(2.0, 4.0, 2.0));
+    match DensityGrid::load_raw("smoke.raw", bounds) {
+        Ok(grid) => objects.push(HeterogeneousMedium::new(boundary, grid, phase_function)),
+        Err(_) => objects.push(HeterogeneousMedium::new(
+            boundary,
+            PerlinDensity::with_noise(Perlin::with_rng(&mut rng), 1.0, 2.0),
+            phase_function,
+        )),
+    }
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn cellular_spheres(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let cracks = RampTexture::from_noise(
+        Worley::with_rng(&mut rng, WorleyMode::F2MinusF1),
+        2.0,
+        ColorRamp::new(vec![
+            (0.0, Color::new(0.05, 0.04, 0.03)),
+            (0.08, Color::new(0.35, 0.3, 0.25)),
+            (0.5, Color::new(0.6, 0.55, 0.5)),
+        ]),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(cracks),
+    ));
+
+    let scales = RampTexture::from_noise(
+        Worley::with_rng(&mut rng, WorleyMode::F1),
+        6.0,
+        ColorRamp::gradient(Color::new(0.1, 0.5, 0.2), Color::new(0.01, 0.1, 0.03)),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 2.0, -2.0),
+        2.0,
+        Lambertian::new(scales),
+    ));
+
+    let terrain = RampTexture::from_noise(
+        RidgedMultifractal::new(Perlin::with_rng(&mut rng), 6, 2.0, 0.5),
+        1.5,
+        ColorRamp::new(vec![
+            (0.6, Color::new(0.1, 0.2, 0.5)),
+            (0.9, Color::new(0.2, 0.4, 0.1)),
+            (1.3, Color::new(0.4, 0.3, 0.2)),
+            (1.8, Color::new(0.9, 0.9, 0.9)),
+        ]),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 2.0, 2.0),
+        2.0,
+        Lambertian::new(terrain),
+    ));
+
+    // Octaves of cells within cells give a pitted pebble.
+    let pebble = RampTexture::from_noise(
+        Fbm::new(Worley::with_rng(&mut rng, WorleyMode::F2), 3, 2.0, 0.5),
+        3.0,
+        ColorRamp::new(vec![
+            (0.9, Color::new(0.15, 0.12, 0.1)),
+            (1.9, Color::new(0.7, 0.65, 0.6)),
+        ]),
+    );
+    objects.push(Sphere::new(
+        Point::new(3.0, 0.6, 0.0),
+        0.6,
+        Lambertian::new(pebble),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn ramp_spheres(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    // A checkerboard darkened by patches of dirt.
+    let dirt = ClampTexture::new(
+        MathTexture::new(
+            NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 0.5, NoiseMode::Turbulence),
+            SolidColor::from_rgb(2.5, 2.5, 2.5),
+            MathOp::Multiply,
+        ),
+        0.0,
+        1.0,
+    );
+    let ground = MixTexture::new(
+        CheckerTexture::from_colors(Color::new(0.2, 0.2, 0.2), Color::new(0.8, 0.8, 0.8)),
+        SolidColor::from_rgb(0.15, 0.1, 0.05),
+        dirt,
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(ground),
+    ));
+
+    let bands = RampTexture::from_texture(
+        NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 2.0, NoiseMode::Turbulence),
+        ColorRamp::new(vec![
+            (0.0, Color::new(0.1, 0.1, 0.4)),
+            (0.2, Color::new(0.2, 0.5, 0.7)),
+            (0.4, Color::new(0.9, 0.8, 0.5)),
+            (0.6, Color::new(0.7, 0.2, 0.1)),
+        ])
+        .with_interpolation(RampInterpolation::Constant),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, -2.2),
+        1.0,
+        Lambertian::new(bands),
+    ));
+
+    let rim = RampTexture::new(
+        RampInput::FacingRatio,
+        ColorRamp::gradient(Color::new(0.9, 0.6, 0.1), Color::new(0.05, 0.05, 0.2))
+            .with_interpolation(RampInterpolation::Smoothstep),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::new(rim),
+    ));
+
+    let height = RampTexture::new(
+        RampInput::Height { min: 0.0, max: 2.0 },
+        ColorRamp::new(vec![
+            (0.0, Color::new(0.1, 0.3, 0.1)),
+            (0.5, Color::new(0.5, 0.4, 0.3)),
+            (0.9, Color::new(0.9, 0.9, 0.9)),
+        ]),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 2.2),
+        1.0,
+        Lambertian::new(height),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn bumpy_spheres(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    let cells = RampTexture::from_noise(
+        Worley::with_rng(&mut rng, WorleyMode::F1),
+        4.0,
+        ColorRamp::gradient(Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, -2.2),
+        1.0,
+        Bumped::bump_map(
+            Lambertian::from_color(Color::new(0.7, 0.3, 0.2)),
+            cells,
+            0.3,
+        ),
+    ));
+
+    // Hammered metal from a normal map tilted along x and y by two independent noises.
+    let tilt = |rng: &mut ChaCha8Rng| {
+        MixTexture::new(
+            SolidColor::from_rgb(0.5, 0.5, 0.5),
+            NoiseTexture::with_noise(Perlin::with_rng(rng), 6.0, NoiseMode::Plain),
+            SolidColor::from_rgb(0.4, 0.4, 0.4),
+        )
+    };
+    let hammered = CombineTexture::new(
+        tilt(&mut rng),
+        tilt(&mut rng),
+        SolidColor::from_rgb(1.0, 1.0, 1.0),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 0.0),
+        1.0,
+        Bumped::normal_map(Metal::new(Color::new(0.8, 0.8, 0.9), 0.0), hammered),
+    ));
+
+    let dents = NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 3.0, NoiseMode::Turbulence);
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 2.2),
+        1.0,
+        Bumped::bump_map(
+            Lambertian::from_color(Color::new(0.4, 0.4, 0.8)),
+            dents,
+            -0.2,
+        ),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn cutout_quads(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::from_color(Color::new(0.4, 0.4, 0.35)),
+    ));
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::from_color(Color::new(0.7, 0.2, 0.1)),
+    ));
+
+    // A lattice in front of the sphere.
+    let lattice = CheckerTexture::from_colors(Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0))
+        .with_space(CheckerSpace::Uv)
+        .with_frequency(12.0);
+    objects.push(Cutout::new(
+        Quad::new(
+            Point::new(2.5, 0.0, -3.0),
+            Vec3::new(0.0, 0.0, 6.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Lambertian::from_color(Color::new(0.9, 0.9, 0.8)),
+        ),
+        lattice,
+        AlphaMode::Threshold(0.5),
+    ));
+
+    // A card of foliage with soft edges behind it.
+    let foliage = RampTexture::from_texture(
+        NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 2.0, NoiseMode::Turbulence),
+        ColorRamp::gradient(Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0))
+            .with_interpolation(RampInterpolation::Smoothstep),
+    );
+    objects.push(Cutout::new(
+        Quad::new(
+            Point::new(-3.0, 0.0, -5.0),
+            Vec3::new(0.0, 0.0, 10.0),
+            Vec3::new(0.0, 4.0, 0.0),
+            Lambertian::from_color(Color::new(0.1, 0.5, 0.1)),
+        ),
+        ClampTexture::new(
+            MathTexture::new(
+                foliage,
+                SolidColor::from_rgb(3.0, 3.0, 3.0),
+                MathOp::Multiply,
+            ),
+            0.0,
+            1.0,
+        ),
+        AlphaMode::Stochastic,
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn triplanar_spheres(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let tiles = || {
+        CheckerTexture::from_colors(Color::new(0.1, 0.2, 0.5), Color::new(0.9, 0.9, 0.9))
+            .with_space(CheckerSpace::Uv)
+            .with_frequency(1.0)
+    };
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+    ));
+    // Spherical texture coordinates pinch towards the poles.
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, -1.2),
+        1.0,
+        Lambertian::new(tiles().with_frequency(12.0)),
+    ));
+    // Projected along the axes, the tiles keep their size all over the sphere.
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 1.2),
+        1.0,
+        Lambertian::new(TriplanarTexture::new(tiles(), 4.0, 4.0)),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn procedural_materials(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::from_color(Color::new(0.4, 0.4, 0.4)),
+    ));
+
+    let bricks = BrickTexture::new(
+        Perlin::with_rng(&mut rng),
+        Color::new(0.55, 0.2, 0.1),
+        Color::new(0.7, 0.7, 0.65),
+    )
+    .with_size(0.05, 0.02)
+    .with_mortar_width(0.003)
+    .with_variation(0.5);
+    objects.push(Quad::new(
+        Point::new(-3.0, 0.0, -6.0),
+        Vec3::new(0.0, 0.0, 12.0),
+        Vec3::new(0.0, 5.0, 0.0),
+        Lambertian::new(bricks),
+    ));
+
+    // Rings around the axis of a trunk through the sphere, tilted to show them at an angle.
+    let wood = WoodTexture::new(
+        Perlin::with_rng(&mut rng),
+        Color::new(0.75, 0.5, 0.3),
+        Color::new(0.4, 0.2, 0.08),
+    )
+    .with_rings(6.0)
+    .with_distortion(0.2)
+    .with_grain(0.3);
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, -2.2),
+        1.0,
+        Lambertian::new(TransformTexture::object(
+            wood,
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            70.0,
+            Vec3::new(0.3, 0.0, 2.0),
+        )),
+    ));
+
+    let marble = MarbleTexture::new(
+        Perlin::with_rng(&mut rng),
+        Color::new(0.9, 0.9, 0.88),
+        Color::new(0.2, 0.2, 0.25),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::new(marble),
+    ));
+
+    let green_marble = MarbleTexture::new(
+        Perlin::with_rng(&mut rng),
+        Color::new(0.1, 0.3, 0.15),
+        Color::new(0.8, 0.9, 0.8),
+    )
+    .with_frequency(2.0)
+    .with_turbulence(4.0)
+    .with_sharpness(20.0);
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 2.2),
+        1.0,
+        Lambertian::new(green_marble),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn textured_parameters(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    // Brushed metal, polished where the noise is low.
+    let roughness = NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 3.0, NoiseMode::Marble);
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, -2.2),
+        1.0,
+        Metal::textured(SolidColor::from_rgb(0.8, 0.6, 0.2), roughness),
+    ));
+
+    // Gold inlays in painted wood.
+    let inlays = CheckerTexture::from_colors(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))
+        .with_space(CheckerSpace::Uv)
+        .with_frequency(8.0);
+    let base_color = MixTexture::new(
+        SolidColor::from_rgb(0.1, 0.2, 0.5),
+        SolidColor::from_rgb(1.0, 0.8, 0.4),
+        inlays.clone(),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 0.0),
+        1.0,
+        MetallicRoughness::new(base_color, inlays, SolidColor::from_rgb(0.1, 0.1, 0.1)),
+    ));
+
+    // Glass whose index of refraction grows with height.
+    let ir = RampTexture::new(
+        RampInput::Height { min: 0.0, max: 2.0 },
+        ColorRamp::gradient(Color::new(1.1, 1.1, 1.1), Color::new(2.0, 2.0, 2.0)),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 2.2),
+        1.0,
+        Dielectric::textured(ir),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn displaced_terrain(seed: u64) -> BVHNode {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    // A low-poly plane, refined and then raised into mountains by a height map.
+    let height = RampTexture::from_noise(
+        RidgedMultifractal::new(Perlin::with_rng(&mut rng), 6, 2.0, 0.5),
+        0.15,
+        ColorRamp::new(vec![
+            (1.0, Color::new(0.0, 0.0, 0.0)),
+            (2.0, Color::new(1.0, 1.0, 1.0)),
+        ]),
+    );
+    let mut terrain = Mesh::grid(
+        Point::new(-10.0, 0.0, 10.0),
+        Vec3::new(20.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -20.0),
+        8,
+        8,
+    );
+    for _ in 0..3 {
+        terrain = terrain.subdivide();
+    }
+    let terrain = terrain.displace(&height, 4.0);
+
+    let colors = RampTexture::new(
+        RampInput::Height { min: 0.0, max: 4.0 },
+        ColorRamp::new(vec![
+            (0.05, Color::new(0.1, 0.2, 0.5)),
+            (0.1, Color::new(0.2, 0.4, 0.1)),
+            (0.5, Color::new(0.4, 0.3, 0.2)),
+            (0.8, Color::new(0.9, 0.9, 0.9)),
+        ]),
+    );
+    BVHNode::with_rng(
+        &terrain.triangles(Lambertian::new(colors)),
+        0.0,
+        1.0,
+        &mut rng,
+    )
+}
+
+fn heightfield_terrain(seed: u64) -> BVHNode {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let height = RampTexture::from_noise(
+        RidgedMultifractal::new(Perlin::with_rng(&mut rng), 8, 2.0, 0.5),
+        0.15,
+        ColorRamp::new(vec![
+            (1.0, Color::new(0.0, 0.0, 0.0)),
+            (2.0, Color::new(1.0, 1.0, 1.0)),
+        ]),
+    );
+    let colors = RampTexture::new(
+        RampInput::Height { min: 0.0, max: 4.0 },
+        ColorRamp::new(vec![
+            (0.05, Color::new(0.1, 0.2, 0.5)),
+            (0.1, Color::new(0.2, 0.4, 0.1)),
+            (0.5, Color::new(0.4, 0.3, 0.2)),
+            (0.8, Color::new(0.9, 0.9, 0.9)),
+        ]),
+    );
+
+    // Elevations from a grayscale heightmap.png in the working directory if there is one, and a
+    // million of them from noise otherwise, far more than would fit in a BVH of triangles.
+    let (corner, size) = (Point::new(-10.0, 0.0, -10.0), Vec3::new(20.0, 4.0, 20.0));
+    let terrain = Heightfield::load(
+        "heightmap.png",
+        corner,
+        size,
+        Lambertian::new(colors.clone()),
+    )
+    .unwrap_or_else(|_| {
+        Heightfield::from_texture(&height, 1025, 1025, corner, size, Lambertian::new(colors))
+    });
+    let mut objects = HitList::new();
+    objects.push(terrain);
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn boxes(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    // A brick block, modeled around the origin and turned into place.
+    let bricks = BrickTexture::new(
+        Perlin::with_rng(&mut rng),
+        Color::new(0.55, 0.2, 0.1),
+        Color::new(0.7, 0.7, 0.65),
+    )
+    .with_size(0.25, 0.1)
+    .with_mortar_width(0.015);
+    let block = Cuboid::new(
+        Point::new(-1.0, 0.0, -1.0),
+        Point::new(1.0, 2.0, 1.0),
+        Lambertian::new(bricks),
+    );
+    objects.push(Translate::new(
+        RotateY::new(block, 30.0),
+        Vec3::new(0.0, 0.0, -1.8),
+    ));
+
+    let tiles = CheckerTexture::from_colors(Color::new(0.1, 0.1, 0.1), Color::new(0.9, 0.9, 0.9))
+        .with_space(CheckerSpace::Uv)
+        .with_frequency(4.0);
+    let cube = Cuboid::new(
+        Point::new(-0.6, 0.0, -0.6),
+        Point::new(0.6, 1.2, 0.6),
+        Lambertian::new(tiles),
+    );
+    objects.push(Translate::new(
+        RotateY::new(cube, -20.0),
+        Vec3::new(1.0, 0.0, 1.2),
+    ));
+
+    objects.push(Cuboid::new(
+        Point::new(-2.0, 0.0, 2.0),
+        Point::new(-0.5, 0.8, 3.0),
+        Dielectric::new(1.5),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn quadrics(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    let checker = CheckerTexture::from_colors(Color::new(0.1, 0.1, 0.1), Color::new(0.9, 0.9, 0.9))
+        .with_space(CheckerSpace::Uv)
+        .with_frequency(8.0);
+    objects.push(
+        Cylinder::new(
+            Point::new(0.0, 0.0, -2.0),
+            0.8,
+            1.6,
+            Lambertian::new(checker.clone()),
+        )
+        .with_caps(true),
+    );
+    // An open three-quarter cylinder shows its inside.
+    objects.push(
+        Cylinder::new(
+            Point::new(0.0, 0.0, 0.2),
+            0.7,
+            1.2,
+            Lambertian::from_color(Color::new(0.8, 0.4, 0.1)),
+        )
+        .with_phi_max(270.0),
+    );
+    // A capped cone with a wedge cut out.
+    objects.push(
+        Cone::new(
+            Point::new(0.5, 0.0, 2.2),
+            0.7,
+            1.8,
+            Lambertian::new(checker),
+        )
+        .with_caps(true)
+        .with_phi_max(300.0),
+    );
+    objects.push(Disk::annulus(
+        Point::new(3.0, 0.01, 0.0),
+        0.4,
+        0.9,
+        Metal::new(Color::new(0.8, 0.8, 0.9), 0.1),
+    ));
+    // A peg rising out of the ring and a cone sinking into the ground blur like the disk.
+    objects.push(
+        Cylinder::new_moving(
+            Point::new(3.0, 0.0, 0.0),
+            Point::new(3.0, 0.3, 0.0),
+            0.0,
+            1.0,
+            0.3,
+            0.5,
+            Lambertian::from_color(Color::new(0.7, 0.1, 0.1)),
+        )
+        .with_caps(true),
+    );
+    objects.push(Cone::new_moving(
+        Point::new(3.0, 0.0, -1.6),
+        Point::new(3.0, -0.3, -1.6),
+        0.0,
+        1.0,
+        0.5,
+        1.0,
+        Lambertian::from_color(Color::new(0.1, 0.6, 0.2)),
+    ));
+    // A pie with a slice taken out.
+    objects.push(
+        Disk::new(
+            Point::new(4.2, 0.01, 1.4),
+            0.5,
+            Lambertian::from_color(Color::new(0.9, 0.7, 0.2)),
+        )
+        .with_phi_max(300.0),
+    );
+    // The disk blurs as it rises.
+    objects.push(Disk::new_moving(
+        Point::new(3.0, 1.0, 1.6),
+        Point::new(3.0, 1.3, 1.6),
+        0.0,
+        1.0,
+        0.0,
+        0.5,
+        Lambertian::from_color(Color::new(0.2, 0.3, 0.8)),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn tori(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    let checker = CheckerTexture::from_colors(Color::new(0.1, 0.1, 0.1), Color::new(0.9, 0.9, 0.9))
+        .with_space(CheckerSpace::Uv)
+        .with_frequency(12.0);
+    objects.push(Torus::new(
+        Point::new(0.0, 0.5, -2.0),
+        1.0,
+        0.5,
+        Lambertian::new(checker),
+    ));
+    // A stack of thin rings around a glass ball.
+    for i in 0..3 {
+        objects.push(Torus::new(
+            Point::new(0.0, 0.15 + 0.3 * i as f64, 1.0),
+            1.0 - 0.2 * i as f64,
+            0.15,
+            Metal::new(Color::new(0.8, 0.6, 0.2), 0.05),
+        ));
+    }
+    objects.push(Sphere::new(
+        Point::new(0.0, 0.8, 1.0),
+        0.5,
+        Dielectric::new(1.5),
+    ));
+    objects.push(Torus::new(
+        Point::new(3.0, 0.3, 0.0),
+        0.6,
+        0.3,
+        Dielectric::new(1.5),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+/// The distance estimate of the power 8 Mandelbulb around the origin, with its pole on the y
+/// axis.
+fn mandelbulb(p: &Point) -> f64 {
+    let power = 8.0;
+    let mut z = p.0;
+    let (mut dr, mut r) = (1.0, 0.0);
+    for _ in 0..12 {
+        r = z.length();
+        if r > 2.0 {
+            break;
+        }
+        let theta = f64::acos(z[1] / r) * power;
+        let phi = f64::atan2(z[2], z[0]) * power;
+        dr = power * r.powf(power - 1.0) * dr + 1.0;
+        z = r.powf(power)
+            * Vec3::new(
+                f64::sin(theta) * f64::cos(phi),
+                f64::cos(theta),
+                f64::sin(theta) * f64::sin(phi),
+            )
+            + p.0;
+    }
+    0.5 * f64::ln(r) * r / dr
+}
+
+fn distance_fields(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    let center = Point::new(0.0, 1.1, 0.0);
+    let bulb = SdfFn::new(
+        AABB::new(
+            center - Vec3::new(1.2, 1.2, 1.2),
+            center + Vec3::new(1.2, 1.2, 1.2),
+        ),
+        move |p: &Point| mandelbulb(&Point(*p - center)),
+    );
+    objects.push(
+        SdfObject::new(bulb, Lambertian::from_color(Color::new(0.8, 0.5, 0.3)))
+            .with_epsilon(1e-3)
+            .with_max_steps(200),
+    );
+
+    // Two spheres melting into each other.
+    let blob = SdfCombine::new(
+        SdfSphere::new(Point::new(0.0, 0.5, 2.1), 0.5),
+        SdfSphere::new(Point::new(0.0, 1.1, 2.5), 0.35),
+        SdfOp::Union,
+    )
+    .with_smoothness(0.4);
+    objects.push(SdfObject::new(
+        blob,
+        Metal::new(Color::new(0.7, 0.7, 0.8), 0.05),
+    ));
+
+    // A rounded box with a ball scooped out, and a ring around a capsule.
+    let scooped = SdfCombine::new(
+        SdfBox::rounded(Point::new(0.0, 0.6, -2.4), Vec3::new(0.6, 0.6, 0.6), 0.1),
+        SdfSphere::new(Point::new(0.0, 1.2, -2.4), 0.5),
+        SdfOp::Subtraction,
+    )
+    .with_smoothness(0.1);
+    objects.push(SdfObject::new(
+        scooped,
+        Lambertian::from_color(Color::new(0.2, 0.4, 0.7)),
+    ));
+    let ring = SdfCombine::new(
+        Capsule::new(Point::new(3.0, 0.3, 0.0), Point::new(3.0, 1.3, 0.0), 0.2),
+        SdfTorus::new(Point::new(3.0, 0.8, 0.0), 0.45, 0.1),
+        SdfOp::Union,
+    )
+    .with_smoothness(0.15);
+    objects.push(SdfObject::new(ring, Dielectric::new(1.5)));
+
+    // A box with its corners trimmed by a sphere, softened where the two meet.
+    let die = SdfCombine::new(
+        SdfBox::new(Point::new(3.0, 0.4, -1.8), Vec3::new(0.4, 0.4, 0.4)),
+        SdfSphere::new(Point::new(3.0, 0.4, -1.8), 0.55),
+        SdfOp::Intersection,
+    )
+    .with_smoothness(0.05);
+    objects.push(SdfObject::new(
+        die,
+        Lambertian::from_color(Color::new(0.9, 0.9, 0.85)),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn solids(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    // A ball drilled through, with the top front quarter cut away to show the hole.
+    let drilled = Csg::new(
+        Sphere::new(
+            Point::new(0.0, 1.0, 0.0),
+            1.0,
+            Lambertian::from_color(Color::new(0.7, 0.2, 0.1)),
+        ),
+        Cylinder::new(
+            Point::new(0.0, -0.5, 0.0),
+            0.4,
+            3.0,
+            Lambertian::from_color(Color::new(0.9, 0.7, 0.2)),
+        )
+        .with_caps(true),
+        CsgOp::Difference,
+    );
+    objects.push(Csg::new(
+        drilled,
+        Cuboid::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(2.0, 3.0, 2.0),
+            Lambertian::from_color(Color::new(0.8, 0.8, 0.8)),
+        ),
+        CsgOp::Difference,
+    ));
+
+    // Two overlapping glass balls merged into one solid, without the inner walls two separate
+    // spheres would refract at.
+    objects.push(Csg::new(
+        Sphere::new(Point::new(3.0, 0.5, -0.4), 0.5, Dielectric::new(1.5)),
+        Sphere::new(Point::new(3.0, 0.5, 0.4), 0.5, Dielectric::new(1.5)),
+        CsgOp::Union,
+    ));
+
+    // A glass lens where two large spheres overlap.
+    objects.push(Csg::new(
+        Sphere::new(Point::new(2.0, 1.1, 3.8), 2.0, Dielectric::new(1.5)),
+        Sphere::new(Point::new(-0.4, 1.1, 1.4), 2.0, Dielectric::new(1.5)),
+        CsgOp::Intersection,
+    ));
+
+    // A cube with its corners rounded off by a sphere.
+    objects.push(Csg::new(
+        Cuboid::new(
+            Point::new(-0.8, 0.0, -3.3),
+            Point::new(0.8, 1.6, -1.7),
+            Lambertian::from_color(Color::new(0.2, 0.4, 0.7)),
+        ),
+        Sphere::new(
+            Point::new(0.0, 0.8, -2.5),
+            1.05,
+            Lambertian::from_color(Color::new(0.2, 0.4, 0.7)),
+        ),
+        CsgOp::Intersection,
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn uv_checker_spheres(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let checker = CheckerTexture::from_colors(Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9))
+        .with_space(CheckerSpace::Uv)
+        .with_frequency(16.0);
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+    // The squares stick to the surface as the sphere moves.
+    objects.push(Sphere::new_moving(
+        Point::new(0.0, 1.0, -1.5),
+        Point::new(0.0, 1.5, -1.5),
+        0.0,
+        1.0,
+        1.0,
+        Lambertian::new(checker.clone()),
+    ));
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 1.5),
+        1.0,
+        Lambertian::new(TransformTexture::uv(checker, (1.0, 2.0), 30.0, (0.0, 0.0))),
+    ));
+    // A spatial checker stretched along y and turned about it, which gives diamonds.
+    objects.push(Sphere::new(
+        Point::new(2.5, 0.6, 0.0),
+        0.6,
+        Lambertian::new(TransformTexture::object(
+            CheckerTexture::from_colors(Color::new(0.6, 0.1, 0.1), Color::new(0.9, 0.9, 0.9)),
+            Vec3::new(1.0, 0.5, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            45.0,
+            Vec3::new(0.0, 0.0, 0.0),
+        )),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+fn texture_networks(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::from_colors(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    // Marble bands, and the same bands with their red and blue channels swapped.
+    let bands = RampTexture::from_texture(
+        NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 2.0, NoiseMode::Marble),
+        ColorRamp::gradient(Color::new(0.9, 0.5, 0.1), Color::new(0.05, 0.1, 0.4)),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, -2.2),
+        1.0,
+        Lambertian::new(bands.clone()),
+    ));
+    let swapped = CombineTexture::new(
+        ChannelTexture::new(bands.clone(), Channel::Blue),
+        ChannelTexture::new(bands.clone(), Channel::Green),
+        ChannelTexture::new(bands.clone(), Channel::Red),
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::new(swapped),
+    ));
+
+    // A gray negative of the bands with an orange glow of turbulence added on top.
+    let glow = MathTexture::new(
+        NoiseTexture::with_noise(Perlin::with_rng(&mut rng), 4.0, NoiseMode::Turbulence),
+        SolidColor::from_rgb(0.4, 0.2, 0.0),
+        MathOp::Multiply,
+    );
+    let negative = MathTexture::new(
+        InvertTexture::new(ChannelTexture::new(bands, Channel::Luminance)),
+        glow,
+        MathOp::Add,
+    );
+    objects.push(Sphere::new(
+        Point::new(0.0, 1.0, 2.2),
+        1.0,
+        Lambertian::new(ClampTexture::new(negative, 0.0, 1.0)),
+    ));
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
+}
+
+/// Needs an equirectangular `earthmap.jpg` in the working directory, which is not part of the
+/// repository.
+fn earth(seed: u64) -> image::ImageResult<BVHNode> {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let earth_texture = ImageTexture::load("earthmap.jpg")?;
+    objects.push(Sphere::new(
+        Point::new(0.0, 0.0, 0.0),
+        2.0,
+        Lambertian::new(earth_texture),
+    ));
+
+    Ok(BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng))
+}
+
+fn image_wrap_modes(seed: u64) -> BVHNode {
+    let mut objects = HitList::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    objects.push(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::from_color(Color::new(0.4, 0.4, 0.4)),
+    ));
+
+    // A 4x4 image getting redder to the right and greener to the top.
+    let texels = (0..16)
+        .map(|i| Color::new((i % 4) as f64 / 3.0, (3 - i / 4) as f64 / 3.0, 0.2))
+        .collect();
+    let image = ImageTexture::from_texels(4, 4, texels);
+
+    // Nearest filtering shows the texels as blocks. Bilinear filtering blends them and reaches
+    // half a texel past the edges of the image at the seam and the poles, where repeating blends
+    // in the opposite edge while clamping and mirroring reuse the edge itself.
+    for (z, wrap_mode, filter) in [
+        (3.3, WrapMode::Repeat, Filter::Nearest),
+        (1.1, WrapMode::Repeat, Filter::Bilinear),
+        (-1.1, WrapMode::Clamp, Filter::Bilinear),
+        (-3.3, WrapMode::Mirror, Filter::Bilinear),
+    ] {
+        let texture = image.clone().with_wrap_mode(wrap_mode).with_filter(filter);
+        objects.push(Sphere::new(
+            Point::new(0.0, 1.0, z),
+            1.0,
+            Lambertian::new(texture),
+        ));
+    }
+
+    BVHNode::with_rng(&objects, 0.0, 1.0, &mut rng)
 }
 
 fn main() {
@@ -155,31 +1390,195 @@ fn main() {
     let world;
     let lookfrom;
     let lookat;
-    let mut vfov = 40.0;
+    let vfov;
     let mut aperture = 0.0;
+    let mut fog = Fog::none();
+    let mut sky = Sky::Gradient;
+    let mut lights: Vec<Arc<dyn Light + Sync + Send>> = Vec::new();
+    let mut light_sampling = LightSampling::Bvh;
+    let seed = 0;
 
     match 0 {
         1 => {
-            world = random_scene();
+            world = random_scene(seed);
             lookfrom = Point::new(13.0, 2.0, 3.0);
             lookat = Point::new(0.0, 0.0, 0.0);
             vfov = 20.0;
             aperture = 0.1;
+            fog = Fog::height(0.02, 0.0, 0.5, Color::new(0.9, 0.9, 0.9), 0.2);
+            sky = Sky::Preetham(PreethamSky::new(25.0, 150.0, 3.0));
         }
         2 => {
-            world = two_spheres();
+            world = two_spheres(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 0.0, 0.0);
+            vfov = 20.0;
+        }
+        4 => {
+            world = perlin_smoke(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 2.0, 0.0);
+            vfov = 25.0;
+        }
+        5 => {
+            world = two_perlin_spheres(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 0.0, 0.0);
+            vfov = 20.0;
+            sky = Sky::Uniform(Color::new(0.02, 0.02, 0.03));
+            lights.push(Arc::new(PointLight::new(
+                Point::new(4.0, 4.0, 4.0),
+                Color::new(60.0, 50.0, 40.0),
+            )));
+            lights.push(Arc::new(SpotLight::new(
+                Point::new(0.0, 8.0, -3.0),
+                Vec3::new(0.0, -2.0, 1.0),
+                Color::new(20.0, 60.0, 120.0),
+                30.0,
+                20.0,
+            )));
+            lights.push(Arc::new(DirectionalLight::new(
+                Vec3::new(-1.0, 1.0, 0.0),
+                Color::new(0.3, 0.3, 0.2),
+                0.0,
+            )));
+            // Too few lights for a light BVH to pay off.
+            light_sampling = LightSampling::Power;
+        }
+        6 => {
+            (world, lights) = glowing_spheres(seed);
             lookfrom = Point::new(13.0, 2.0, 3.0);
             lookat = Point::new(0.0, 0.0, 0.0);
             vfov = 20.0;
+            sky = Sky::Uniform(Color::new(0.0, 0.0, 0.0));
         }
-        3 | _ => {
-            world = two_perlin_spheres();
+        7 => {
+            world = earth(seed).unwrap_or_else(|err| {
+                eprintln!(
+                    "Could not load earthmap.jpg ({}), rendering the default scene.",
+                    err
+                );
+                two_perlin_spheres(seed)
+            });
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 0.0, 0.0);
+            vfov = 20.0;
+        }
+        8 => {
+            world = image_wrap_modes(seed);
+            lookfrom = Point::new(13.0, 2.0, 0.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 35.0;
+        }
+        9 => {
+            world = cellular_spheres(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 30.0;
+        }
+        10 => {
+            world = uv_checker_spheres(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        11 => {
+            world = ramp_spheres(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        12 => {
+            world = texture_networks(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        13 => {
+            world = bumpy_spheres(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        14 => {
+            world = cutout_quads(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 30.0;
+        }
+        15 => {
+            world = triplanar_spheres(seed);
+            lookfrom = Point::new(6.0, 6.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 35.0;
+        }
+        16 => {
+            world = procedural_materials(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        17 => {
+            world = textured_parameters(seed);
+            lookfrom = Point::new(13.0, 2.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        18 => {
+            world = displaced_terrain(seed);
+            lookfrom = Point::new(14.0, 9.0, 14.0);
+            lookat = Point::new(0.0, 0.0, 0.0);
+            vfov = 40.0;
+        }
+        19 => {
+            world = heightfield_terrain(seed);
+            lookfrom = Point::new(14.0, 9.0, 14.0);
+            lookat = Point::new(0.0, 0.0, 0.0);
+            vfov = 40.0;
+        }
+        20 => {
+            world = boxes(seed);
+            lookfrom = Point::new(13.0, 4.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        21 => {
+            world = quadrics(seed);
+            lookfrom = Point::new(13.0, 4.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        22 => {
+            world = tori(seed);
+            lookfrom = Point::new(13.0, 5.0, 3.0);
+            lookat = Point::new(0.0, 0.5, 0.0);
+            vfov = 25.0;
+        }
+        23 => {
+            world = distance_fields(seed);
+            lookfrom = Point::new(13.0, 4.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        24 => {
+            world = solids(seed);
+            lookfrom = Point::new(13.0, 4.0, 3.0);
+            lookat = Point::new(0.0, 1.0, 0.0);
+            vfov = 25.0;
+        }
+        _ => {
+            world = two_perlin_spheres(seed);
             lookfrom = Point::new(13.0, 2.0, 3.0);
             lookat = Point::new(0.0, 0.0, 0.0);
             vfov = 20.0;
         }
     }
 
+    if let Some(sun) = sky.sun() {
+        lights.push(sun);
+    }
+    let lights = LightSampler::new(lights, light_sampling);
+
     let cam = Camera::new(
         lookfrom,
         lookat,
@@ -204,8 +1603,13 @@ fn main() {
                     let v =
                         (j as f64 + rand::thread_rng().gen::<f64>()) / (image_height as f64 - 1.0);
 
-                    let r = cam.get_ray(u, v);
-                    ray_color(&r, &world, max_depth)
+                    let r = cam.get_ray_differential(
+                        u,
+                        v,
+                        1.0 / (image_width as f64 - 1.0),
+                        1.0 / (image_height as f64 - 1.0),
+                    );
+                    ray_color(&r, &world, &lights, &sky, &fog, max_depth, true)
                 })
                 .sum();
 
@@ -1,12 +1,31 @@
 use crate::color::Color;
 use crate::hit::HitRecord;
+use crate::point::Point;
 use crate::ray::Ray;
 use crate::texture::{SolidColor, Texture};
 use crate::vec3::Vec3;
 use rand::Rng;
+use std::f64::consts::PI;
 
 pub trait Material {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Ray, Color)>;
+
+    fn emitted(&self, _u: f64, _v: f64, _p: &Point) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Returns the BSDF times the cosine term (or the phase function for media) for scattering
+    /// `r_in` towards the unit vector `direction`. Only needed by non-specular materials.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Specular materials only scatter in discrete directions, so they can't gather light from
+    /// shadow rays and must see light sources through `scatter` instead. Takes the hit, as
+    /// materials can be specular in some places and not in others.
+    fn is_specular(&self, _rec: &HitRecord) -> bool {
+        true
+    }
 }
 
 pub struct Lambertian {
@@ -35,47 +54,85 @@ impl Material for Lambertian {
             scatter_direction = rec.normal
         }
 
-        Some((Ray::new(rec.p, scatter_direction, r_in.time()), self.albedo.value(rec.u, rec.v, &rec.p)))
+        Some((Ray::new(rec.p, scatter_direction, r_in.time()), self.albedo.hit_value(rec)))
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        let cosine = f64::max(rec.normal.dot(direction), 0.0);
+        (cosine / PI) * self.albedo.hit_value(rec)
+    }
+
+    fn is_specular(&self, _rec: &HitRecord) -> bool {
+        false
+    }
+}
+
+/// Reads a scalar material parameter from the luminance of a texture.
+fn scalar(texture: &(dyn Texture + Sync + Send), rec: &HitRecord) -> f64 {
+    texture.hit_value(rec).luminance()
+}
+
+/// Reflects `r_in` around the normal and perturbs the reflection within a sphere of radius
+/// `fuzz`, absorbing rays perturbed into the surface.
+fn fuzzy_reflection(r_in: &Ray, rec: &HitRecord, fuzz: f64) -> Option<Ray> {
+    let reflected = r_in.direction().unit_vector().reflect(&rec.normal);
+    let scattered = Ray::new(
+        rec.p,
+        reflected + fuzz * Vec3::random_in_unit_sphere().0,
+        r_in.time(),
+    );
+    if scattered.direction().dot(&rec.normal) > 0.0 {
+        Some(scattered)
+    } else {
+        None
     }
 }
 
 pub struct Metal {
-    albedo: Color,
-    fuzz: f64,
+    albedo: Box<dyn Texture + Sync + Send>,
+    fuzz: Box<dyn Texture + Sync + Send>,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::textured(
+            SolidColor::new(albedo),
+            SolidColor::from_rgb(fuzz, fuzz, fuzz),
+        )
+    }
+
+    /// Takes the fuzz from the luminance of a texture, such as a roughness map.
+    pub fn textured(
+        albedo: impl Texture + Sync + Send + 'static,
+        fuzz: impl Texture + Sync + Send + 'static,
+    ) -> Self {
         Self {
-            albedo,
-            fuzz: fuzz.min(1.0),
+            albedo: Box::new(albedo),
+            fuzz: Box::new(fuzz),
         }
     }
 }
 
 impl Material for Metal {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
-        let reflected = r_in.direction().unit_vector().reflect(&rec.normal);
-        let scattered = Ray::new(
-            rec.p,
-            reflected + self.fuzz * Vec3::random_in_unit_sphere().0,
-            r_in.time(),
-        );
-        if scattered.direction().dot(&rec.normal) > 0.0 {
-            Some((scattered, self.albedo))
-        } else {
-            None
-        }
+        let fuzz = scalar(self.fuzz.as_ref(), rec).clamp(0.0, 1.0);
+        let scattered = fuzzy_reflection(r_in, rec, fuzz)?;
+        Some((scattered, self.albedo.hit_value(rec)))
     }
 }
 
 pub struct Dielectric {
-    ir: f64,
+    ir: Box<dyn Texture + Sync + Send>,
 }
 
 impl Dielectric {
     pub fn new(ir: f64) -> Dielectric {
-        Dielectric { ir }
+        Dielectric::textured(SolidColor::from_rgb(ir, ir, ir))
+    }
+
+    /// Takes the index of refraction from the luminance of a texture.
+    pub fn textured(ir: impl Texture + Sync + Send + 'static) -> Dielectric {
+        Dielectric { ir: Box::new(ir) }
     }
 
     fn reflectance(cosine: f64, refraction_ratio: f64) -> f64 {
@@ -88,11 +145,8 @@ impl Dielectric {
 
 impl Material for Dielectric {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
-        };
+        let ir = scalar(self.ir.as_ref(), rec);
+        let refraction_ratio = if rec.front_face { 1.0 / ir } else { ir };
         let unit_direction = r_in.direction().unit_vector();
         let cos_theta = f64::min(-unit_direction.dot(&rec.normal), 1.0);
         let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
@@ -111,3 +165,195 @@ impl Material for Dielectric {
         ))
     }
 }
+
+/// A material following the metallic-roughness convention of asset pipelines such as glTF. The
+/// metallic texture is a mask: hits where its luminance is at least 0.5 reflect like a metal
+/// tinted by the base color and blurred by the roughness, and the others are diffuse.
+pub struct MetallicRoughness {
+    base_color: Box<dyn Texture + Sync + Send>,
+    metallic: Box<dyn Texture + Sync + Send>,
+    roughness: Box<dyn Texture + Sync + Send>,
+}
+
+impl MetallicRoughness {
+    pub fn new(
+        base_color: impl Texture + Sync + Send + 'static,
+        metallic: impl Texture + Sync + Send + 'static,
+        roughness: impl Texture + Sync + Send + 'static,
+    ) -> Self {
+        Self {
+            base_color: Box::new(base_color),
+            metallic: Box::new(metallic),
+            roughness: Box::new(roughness),
+        }
+    }
+
+    fn is_metal(&self, rec: &HitRecord) -> bool {
+        scalar(self.metallic.as_ref(), rec) >= 0.5
+    }
+}
+
+impl Material for MetallicRoughness {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+        let direction = if self.is_metal(rec) {
+            let roughness = scalar(self.roughness.as_ref(), rec).clamp(0.0, 1.0);
+            *fuzzy_reflection(r_in, rec, roughness)?.direction()
+        } else {
+            let direction = rec.normal + Vec3::random_unit_vector();
+            if direction.near_zero() {
+                rec.normal
+            } else {
+                direction
+            }
+        };
+
+        Some((
+            Ray::new(rec.p, direction, r_in.time()),
+            self.base_color.hit_value(rec),
+        ))
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        if self.is_metal(rec) {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let cosine = f64::max(rec.normal.dot(direction), 0.0);
+        (cosine / PI) * self.base_color.hit_value(rec)
+    }
+
+    fn is_specular(&self, rec: &HitRecord) -> bool {
+        self.is_metal(rec)
+    }
+}
+
+/// Phase function for scattering inside participating media. `g` in (-1, 1) controls the
+/// anisotropy: positive values scatter forward, negative values backward and 0 is isotropic.
+pub struct HenyeyGreenstein {
+    albedo: Box<dyn Texture + Sync + Send>,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: impl Texture + Sync + Send + 'static, g: f64) -> Self {
+        Self {
+            albedo: Box::new(albedo),
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+
+    pub fn from_color(albedo: Color, g: f64) -> Self {
+        Self::new(SolidColor::new(albedo), g)
+    }
+
+    /// Probability density of scattering by an angle whose cosine is `cos_theta`, measured from
+    /// the direction of propagation.
+    pub fn phase(&self, cos_theta: f64) -> f64 {
+        let denom = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+        (1.0 - self.g * self.g) / (4.0 * PI * denom * f64::sqrt(denom))
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        let xi: f64 = rand::thread_rng().gen();
+        if f64::abs(self.g) < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let sqr_term = (1.0 - self.g * self.g) / (1.0 - self.g + 2.0 * self.g * xi);
+            ((1.0 + self.g * self.g - sqr_term * sqr_term) / (2.0 * self.g)).clamp(-1.0, 1.0)
+        }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+        let forward = r_in.direction().unit_vector();
+        let (t, b) = forward.orthonormal_basis();
+
+        let cos_theta = self.sample_cos_theta();
+        let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+        let phi = 2.0 * PI * rand::thread_rng().gen::<f64>();
+        let direction =
+            sin_theta * f64::cos(phi) * t + sin_theta * f64::sin(phi) * b + cos_theta * forward;
+
+        Some((
+            Ray::new(rec.p, direction, r_in.time()),
+            self.albedo.hit_value(rec),
+        ))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Color {
+        let cos_theta = r_in.direction().unit_vector().dot(direction);
+        self.phase(cos_theta) * self.albedo.hit_value(rec)
+    }
+
+    fn is_specular(&self, _rec: &HitRecord) -> bool {
+        false
+    }
+}
+
+/// An emissive material. Emissive objects should also be registered as lights, as surfaces
+/// that sample lights with shadow rays ignore the emission they hit by scattering.
+pub struct DiffuseLight {
+    emit: Box<dyn Texture + Sync + Send>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: impl Texture + Sync + Send + 'static) -> Self {
+        Self {
+            emit: Box::new(emit),
+        }
+    }
+
+    pub fn from_color(color: Color) -> Self {
+        Self::new(SolidColor::new(color))
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.emit.value(u, v, p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::{CheckerSpace, CheckerTexture};
+
+    #[test]
+    fn test_metallic_switches_between_metal_and_diffuse() {
+        // Metal in the bottom left and top right quarters of the texture coordinates.
+        let metallic =
+            CheckerTexture::from_colors(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))
+                .with_space(CheckerSpace::Uv)
+                .with_frequency(2.0);
+        let mat = MetallicRoughness::new(
+            SolidColor::from_rgb(0.8, 0.6, 0.4),
+            metallic,
+            SolidColor::from_rgb(0.0, 0.0, 0.0),
+        );
+        let r = Ray::new(Point::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0), 0.0);
+        let up = Vec3::new(0.0, 1.0, 0.0);
+
+        // A smooth metal reflects the ray like a mirror and can't be lit by shadow rays.
+        let metal = HitRecord::new(&r, up, 1.0, 0.25, 0.25, &mat);
+        assert!(mat.is_specular(&metal));
+        assert_eq!(mat.eval(&r, &metal, &up).0[0], 0.0);
+        let (reflected, attenuation) = mat.scatter(&r, &metal).unwrap();
+        let mirrored = Vec3::new(1.0, 1.0, 0.0).unit_vector();
+        assert!(f64::abs(reflected.direction().unit_vector().dot(&mirrored) - 1.0) < 1e-9);
+        assert_eq!(attenuation.0[0], 0.8);
+
+        // Elsewhere it is Lambertian.
+        let diffuse = HitRecord::new(&r, up, 1.0, 0.75, 0.25, &mat);
+        assert!(!mat.is_specular(&diffuse));
+        assert!(f64::abs(mat.eval(&r, &diffuse, &up).0[0] - 0.8 / PI) < 1e-9);
+        for _ in 0..100 {
+            let (scattered, _) = mat.scatter(&r, &diffuse).unwrap();
+            assert!(scattered.direction().dot(&up) >= 0.0);
+        }
+    }
+}
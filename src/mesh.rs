@@ -0,0 +1,284 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitList, HitRecord};
+use crate::material::Material;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// An indexed triangle mesh with texture coordinates and smooth vertex normals. Meshes are
+/// built up by value, e.g. `Mesh::grid(...).subdivide().displace(...)`, and turned into
+/// hittable triangles with `triangles`.
+#[derive(Clone)]
+pub struct Mesh {
+    positions: Vec<Point>,
+    uvs: Vec<(f64, f64)>,
+    normals: Vec<Vec3>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+    /// Creates a mesh from vertices, their texture coordinates and counterclockwise triangles.
+    /// Vertex normals are averaged from the triangles around each vertex.
+    pub fn new(positions: Vec<Point>, uvs: Vec<(f64, f64)>, indices: Vec<[usize; 3]>) -> Self {
+        assert_eq!(positions.len(), uvs.len(), "Mesh needs one uv per vertex.");
+        assert!(
+            indices.iter().flatten().all(|&i| i < positions.len()),
+            "Mesh index out of range."
+        );
+        let mut mesh = Self {
+            positions,
+            uvs,
+            normals: Vec::new(),
+            indices,
+        };
+        mesh.compute_normals();
+        mesh
+    }
+
+    /// A parallelogram with corner `q` and edges `u` and `v`, split into `nu * nv` cells of two
+    /// triangles each, with texture coordinates from 0 to 1 along the edges.
+    pub fn grid(q: Point, u: Vec3, v: Vec3, nu: usize, nv: usize) -> Self {
+        let mut positions = Vec::with_capacity((nu + 1) * (nv + 1));
+        let mut uvs = Vec::with_capacity((nu + 1) * (nv + 1));
+        for j in 0..=nv {
+            for i in 0..=nu {
+                let (s, t) = (i as f64 / nu as f64, j as f64 / nv as f64);
+                positions.push(q + s * u + t * v);
+                uvs.push((s, t));
+            }
+        }
+
+        let vertex = |i: usize, j: usize| j * (nu + 1) + i;
+        let mut indices = Vec::with_capacity(2 * nu * nv);
+        for j in 0..nv {
+            for i in 0..nu {
+                indices.push([vertex(i, j), vertex(i + 1, j), vertex(i + 1, j + 1)]);
+                indices.push([vertex(i, j), vertex(i + 1, j + 1), vertex(i, j + 1)]);
+            }
+        }
+
+        Self::new(positions, uvs, indices)
+    }
+
+    /// Splits every triangle into four at the midpoints of its edges. Neighboring triangles
+    /// share their midpoints, so the mesh stays watertight.
+    pub fn subdivide(&self) -> Self {
+        let mut positions = self.positions.clone();
+        let mut uvs = self.uvs.clone();
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: usize, b: usize| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(Point(0.5 * (self.positions[a].0 + self.positions[b].0)));
+                uvs.push((
+                    0.5 * (self.uvs[a].0 + self.uvs[b].0),
+                    0.5 * (self.uvs[a].1 + self.uvs[b].1),
+                ));
+                positions.len() - 1
+            })
+        };
+
+        let mut indices = Vec::with_capacity(4 * self.indices.len());
+        for &[a, b, c] in &self.indices {
+            let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+            indices.extend([[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]);
+        }
+
+        Self::new(positions, uvs, indices)
+    }
+
+    /// Moves every vertex along its normal by `scale` times the luminance of `height` at the
+    /// vertex, then recomputes the normals. Subdivide first so there are enough vertices to
+    /// carry the detail of the height map.
+    pub fn displace(&self, height: &dyn Texture, scale: f64) -> Self {
+        let positions = self
+            .positions
+            .iter()
+            .zip(&self.uvs)
+            .zip(&self.normals)
+            .map(|((p, &(u, v)), n)| *p + scale * height.value(u, v, p).luminance() * *n)
+            .collect();
+
+        Self::new(positions, self.uvs.clone(), self.indices.clone())
+    }
+
+    /// Averages the normals of the triangles around each vertex, weighted by their area.
+    fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::new(0.0, 0.0, 0.0); self.positions.len()];
+        for &[a, b, c] in &self.indices {
+            let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+            // The length of the cross product is twice the area.
+            let n = (pb - pa).cross(&(pc - pa));
+            for i in [a, b, c] {
+                normals[i] += n;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| if n.near_zero() { n } else { n.unit_vector() })
+            .collect();
+    }
+
+    /// Returns the triangles of the mesh, all sharing `mat`, ready to be put into a `BVHNode`.
+    pub fn triangles(self, mat: impl Material + Sync + Send + 'static) -> HitList {
+        let count = self.indices.len();
+        let data = Arc::new(MeshData {
+            mesh: self,
+            mat: Box::new(mat),
+        });
+
+        let mut list = HitList::new();
+        for index in 0..count {
+            list.push(Triangle {
+                data: data.clone(),
+                index,
+            });
+        }
+        list
+    }
+}
+
+/// Intersects `r` with the triangle `p0`, `p1`, `p2` using the Möller-Trumbore algorithm.
+/// Returns the ray parameter and the barycentric coordinates of `p1` and `p2`.
+pub fn intersect_triangle(
+    r: &Ray,
+    p0: Point,
+    p1: Point,
+    p2: Point,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let e1 = p1 - p0;
+    let e2 = p2 - p0;
+    let pvec = r.direction().cross(&e2);
+    let det = e1.dot(&pvec);
+    if f64::abs(det) < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = *r.origin() - p0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(&e1);
+    let b2 = r.direction().dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = e2.dot(&qvec) * inv_det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+struct MeshData {
+    mesh: Mesh,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+/// One triangle of a `Mesh`, shaded with the interpolated vertex normals.
+pub struct Triangle {
+    data: Arc<MeshData>,
+    index: usize,
+}
+
+impl Triangle {
+    fn vertices(&self) -> [usize; 3] {
+        self.data.mesh.indices[self.index]
+    }
+}
+
+impl Hit for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mesh = &self.data.mesh;
+        let [a, b, c] = self.vertices();
+        let (p0, p1, p2) = (mesh.positions[a], mesh.positions[b], mesh.positions[c]);
+
+        let (t, b1, b2) = intersect_triangle(r, p0, p1, p2, t_min, t_max)?;
+        let b0 = 1.0 - b1 - b2;
+        let (uv0, uv1, uv2) = (mesh.uvs[a], mesh.uvs[b], mesh.uvs[c]);
+        let u = b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0;
+        let v = b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1;
+
+        let geometric_normal = (p1 - p0).cross(&(p2 - p0)).unit_vector();
+        let mut rec = HitRecord::new(r, geometric_normal, t, u, v, self.data.mat.as_ref());
+
+        let shading_normal = b0 * mesh.normals[a] + b1 * mesh.normals[b] + b2 * mesh.normals[c];
+        if !shading_normal.near_zero() {
+            let shading_normal = shading_normal.unit_vector();
+            // Keep the shading normal on the side of the surface the ray came from.
+            rec.normal = if shading_normal.dot(&rec.normal) < 0.0 {
+                -shading_normal
+            } else {
+                shading_normal
+            };
+        }
+
+        // Solve the edges for the partial derivatives along the texture coordinates.
+        let (du02, dv02) = (uv0.0 - uv2.0, uv0.1 - uv2.1);
+        let (du12, dv12) = (uv1.0 - uv2.0, uv1.1 - uv2.1);
+        let uv_det = du02 * dv12 - dv02 * du12;
+        if f64::abs(uv_det) > 1e-12 {
+            let (dp02, dp12) = (p0 - p2, p1 - p2);
+            rec.dpdu = (dv12 * dp02 - dv02 * dp12) / uv_det;
+            rec.dpdv = (du02 * dp12 - du12 * dp02) / uv_det;
+        }
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AABB> {
+        let mesh = &self.data.mesh;
+        let corners = self.vertices().map(|i| mesh.positions[i]);
+        let axis =
+            |i: usize, f: fn(f64, f64) -> f64| corners.iter().map(|c| c.0[i]).reduce(f).unwrap();
+        // Pad the box so it does not have zero thickness for axis-aligned triangles.
+        let padding = 0.0001;
+        Some(AABB::new(
+            Point::new(
+                axis(0, f64::min) - padding,
+                axis(1, f64::min) - padding,
+                axis(2, f64::min) - padding,
+            ),
+            Point::new(
+                axis(0, f64::max) + padding,
+                axis(1, f64::max) + padding,
+                axis(2, f64::max) + padding,
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::SolidColor;
+
+    #[test]
+    fn test_subdivide_and_displace() {
+        let grid = Mesh::grid(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            2,
+            2,
+        );
+        assert_eq!(grid.indices.len(), 8);
+
+        let fine = grid.subdivide();
+        assert_eq!(fine.indices.len(), 32);
+        // Shared midpoints: a 4x4 grid of cells has 25 vertices.
+        assert_eq!(fine.positions.len(), 25);
+
+        let raised = fine.displace(&SolidColor::from_rgb(1.0, 1.0, 1.0), 0.5);
+        for (p, n) in raised.positions.iter().zip(&raised.normals) {
+            assert!(f64::abs(p.0[1] - 0.5) < 1e-12);
+            assert!(f64::abs(n[1] - 1.0) < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::perlin::Perlin;
+use crate::point::Point;
+
+/// A scalar field over space, such as `Perlin` or `Worley` noise.
+pub trait Noise {
+    fn noise(&self, p: &Point) -> f64;
+}
+
+impl Noise for Perlin {
+    fn noise(&self, p: &Point) -> f64 {
+        Perlin::noise(self, p)
+    }
+}
+
+/// Fractional Brownian motion: a sum of `octaves` copies of `noise`, each scaled in frequency by
+/// `lacunarity` and in amplitude by `gain` relative to the previous one.
+#[derive(Clone)]
+pub struct Fbm<N: Noise> {
+    noise: N,
+    octaves: u32,
+    lacunarity: f64,
+    gain: f64,
+}
+
+impl<N: Noise> Fbm<N> {
+    pub fn new(noise: N, octaves: u32, lacunarity: f64, gain: f64) -> Self {
+        Self {
+            noise,
+            octaves,
+            lacunarity,
+            gain,
+        }
+    }
+}
+
+impl<N: Noise> Noise for Fbm<N> {
+    fn noise(&self, p: &Point) -> f64 {
+        let mut sum = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        for _ in 0..self.octaves {
+            sum += amplitude * self.noise.noise(&Point(frequency * p.0));
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        sum
+    }
+}
+
+/// Musgrave's ridged multifractal, which folds each octave of `noise` around zero into sharp
+/// ridges and lets the ridges of lower octaves control the weight of higher ones. Gives values
+/// in about `[0, 2]` for Perlin noise, suited for mountain ranges and cracks.
+#[derive(Clone)]
+pub struct RidgedMultifractal<N: Noise> {
+    noise: N,
+    octaves: u32,
+    lacunarity: f64,
+    gain: f64,
+    offset: f64,
+}
+
+impl<N: Noise> RidgedMultifractal<N> {
+    pub fn new(noise: N, octaves: u32, lacunarity: f64, gain: f64) -> Self {
+        Self {
+            noise,
+            octaves,
+            lacunarity,
+            gain,
+            offset: 1.0,
+        }
+    }
+}
+
+impl<N: Noise> Noise for RidgedMultifractal<N> {
+    fn noise(&self, p: &Point) -> f64 {
+        let mut sum = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut weight = 1.0;
+        for _ in 0..self.octaves {
+            let ridge = self.offset - f64::abs(self.noise.noise(&Point(frequency * p.0)));
+            let signal = ridge * ridge * weight;
+            weight = (signal * 2.0).clamp(0.0, 1.0);
+            sum += amplitude * signal;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        sum
+    }
+}
@@ -1,55 +1,150 @@
 use crate::point::Point;
+use crate::vec3::Vec3;
 use rand::Rng;
 
 const POINT_COUNT: usize = 256;
 
 #[derive(Clone)]
 pub struct Perlin {
-    randfloat: [f64; POINT_COUNT],
+    ranvec: [Vec3; POINT_COUNT],
     perm_x: [i32; POINT_COUNT],
     perm_y: [i32; POINT_COUNT],
     perm_z: [i32; POINT_COUNT],
 }
 
 impl Perlin {
-    pub fn new() -> Perlin {
-        let mut randfloat = [0.0; POINT_COUNT];
-        for x in randfloat.iter_mut() {
-            *x = rand::thread_rng().gen();
+    pub fn with_rng<R: Rng + ?Sized>(rng: &mut R) -> Perlin {
+        let mut ranvec = [Vec3::new(0.0, 0.0, 0.0); POINT_COUNT];
+        for v in ranvec.iter_mut() {
+            *v = Vec3::random_unit_vector_with(rng);
         }
 
         Perlin {
-            randfloat,
-            perm_x: Perlin::perlin_generate_perm(),
-            perm_y: Perlin::perlin_generate_perm(),
-            perm_z: Perlin::perlin_generate_perm(),
+            ranvec,
+            perm_x: Perlin::perlin_generate_perm(rng),
+            perm_y: Perlin::perlin_generate_perm(rng),
+            perm_z: Perlin::perlin_generate_perm(rng),
         }
     }
 
+    /// Gradient noise in `[-1, 1]`, zero on the integer lattice.
     pub fn noise(&self, p: &Point) -> f64 {
-        let i = (((4.0 * p.0[0]) as i32) & 255) as usize;
-        let j = (((4.0 * p.0[1]) as i32) & 255) as usize;
-        let k = (((4.0 * p.0[2]) as i32) & 255) as usize;
+        let u = p.0[0] - p.0[0].floor();
+        let v = p.0[1] - p.0[1].floor();
+        let w = p.0[2] - p.0[2].floor();
 
-        self.randfloat[(self.perm_x[i] ^ self.perm_y[j] ^ self.perm_z[k]) as usize]
+        let i = p.0[0].floor() as i32;
+        let j = p.0[1].floor() as i32;
+        let k = p.0[2].floor() as i32;
+
+        let mut c = [[[Vec3::new(0.0, 0.0, 0.0); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.ranvec[(self.perm_x[((i + di as i32) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i32) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i32) & 255) as usize])
+                        as usize];
+                }
+            }
+        }
+
+        Perlin::perlin_interp(&c, u, v, w)
+    }
+
+    /// Absolute value of the sum of `depth` octaves of noise, each with double the frequency and
+    /// half the amplitude of the previous one.
+    pub fn turb(&self, p: &Point, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p = Point(temp_p.0 * 2.0);
+        }
+
+        f64::abs(accum)
+    }
+
+    /// Trilinearly interpolates the dot products of the corner gradients with the offsets to
+    /// the corners, with Hermite smoothing of the weights.
+    fn perlin_interp(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+
+        let mut accum = 0.0;
+        for (i, plane) in c.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, corner) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                    let weight_v = Vec3::new(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * corner.dot(&weight_v);
+                }
+            }
+        }
+
+        accum
     }
 
-    fn perlin_generate_perm() -> [i32; POINT_COUNT] {
+    fn perlin_generate_perm<R: Rng + ?Sized>(rng: &mut R) -> [i32; POINT_COUNT] {
         let mut res = [0; POINT_COUNT];
 
         for (i, x) in res.iter_mut().enumerate() {
             *x = i as i32;
         }
 
-        res = Perlin::permute(res, POINT_COUNT);
+        res = Perlin::permute(res, POINT_COUNT, rng);
         res
     }
 
-    fn permute(mut p: [i32; POINT_COUNT], n: usize) -> [i32; POINT_COUNT] {
+    fn permute<R: Rng + ?Sized>(
+        mut p: [i32; POINT_COUNT],
+        n: usize,
+        rng: &mut R,
+    ) -> [i32; POINT_COUNT] {
         for i in (1..n).rev() {
-            let target = rand::thread_rng().gen_range(0..i);
+            let target = rng.gen_range(0..i);
             p.swap(i, target);
         }
         p
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_noise_vanishes_on_lattice() {
+        let perlin = Perlin::with_rng(&mut rand::thread_rng());
+        for p in [
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(3.0, -7.0, 12.0),
+            Point::new(-255.0, 256.0, 1.0),
+        ] {
+            assert!(f64::abs(perlin.noise(&p)) < 1e-12);
+        }
+
+        let p = Point::new(0.3, 1.7, -2.2);
+        assert!((-1.0..=1.0).contains(&perlin.noise(&p)));
+    }
+
+    #[test]
+    fn test_seeded_noise_is_reproducible() {
+        let seeded = |seed: u64| Perlin::with_rng(&mut ChaCha8Rng::seed_from_u64(seed));
+        let (a, b, c) = (seeded(7), seeded(7), seeded(8));
+
+        let p = Point::new(0.3, 1.7, -2.2);
+        assert_eq!(a.noise(&p), b.noise(&p));
+        assert_eq!(a.turb(&p, 7), b.turb(&p, 7));
+        assert_ne!(a.noise(&p), c.noise(&p));
+    }
+}
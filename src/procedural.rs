@@ -0,0 +1,226 @@
+//! Classic procedural textures built on Perlin turbulence. They are laid out around the origin
+//! in object space or on the texture coordinates, and can be moved and scaled with a
+//! `TransformTexture`.
+
+use crate::color::Color;
+use crate::perlin::Perlin;
+use crate::point::Point;
+use crate::texture::Texture;
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    (1.0 - t) * a + t * b
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Concentric growth rings around the y axis, wobbled by turbulence and streaked with fine
+/// grain along the axis.
+#[derive(Clone)]
+pub struct WoodTexture {
+    noise: Perlin,
+    light: Color,
+    dark: Color,
+    rings: f64,
+    distortion: f64,
+    grain: f64,
+}
+
+impl WoodTexture {
+    pub fn new(noise: Perlin, light: Color, dark: Color) -> Self {
+        Self {
+            noise,
+            light,
+            dark,
+            rings: 8.0,
+            distortion: 0.1,
+            grain: 0.15,
+        }
+    }
+
+    /// Sets the number of rings per unit of radius.
+    pub fn with_rings(mut self, rings: f64) -> Self {
+        self.rings = rings;
+        self
+    }
+
+    /// Sets how far turbulence moves the rings, in units of radius.
+    pub fn with_distortion(mut self, distortion: f64) -> Self {
+        self.distortion = distortion;
+        self
+    }
+
+    /// Sets how strongly the grain darkens the wood, from 0 to 1.
+    pub fn with_grain(mut self, grain: f64) -> Self {
+        self.grain = grain;
+        self
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let [x, y, z] = [p.0[0], p.0[1], p.0[2]];
+        let radius = f64::sqrt(x * x + z * z) + self.distortion * self.noise.turb(p, 4);
+        let ring = (radius * self.rings).fract();
+        // Late wood at the end of each ring is darker and grows more abruptly.
+        let t = smoothstep(0.0, 0.8, ring) - smoothstep(0.85, 1.0, ring);
+
+        // Grain is noise stretched along the axis of the trunk.
+        let grain = self.noise.noise(&Point::new(40.0 * x, 2.0 * y, 40.0 * z));
+        let grain = 1.0 - self.grain * 0.5 * (1.0 + grain);
+        grain * lerp(self.light, self.dark, t)
+    }
+}
+
+/// Thin veins of `vein` color in a `base` stone, running across the x axis and bent by
+/// turbulence.
+#[derive(Clone)]
+pub struct MarbleTexture {
+    noise: Perlin,
+    base: Color,
+    vein: Color,
+    frequency: f64,
+    turbulence: f64,
+    sharpness: f64,
+}
+
+impl MarbleTexture {
+    pub fn new(noise: Perlin, base: Color, vein: Color) -> Self {
+        Self {
+            noise,
+            base,
+            vein,
+            frequency: 4.0,
+            turbulence: 6.0,
+            sharpness: 8.0,
+        }
+    }
+
+    /// Sets how many veins cross each unit along x, divided by pi.
+    pub fn with_frequency(mut self, frequency: f64) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets how far turbulence bends the veins.
+    pub fn with_turbulence(mut self, turbulence: f64) -> Self {
+        self.turbulence = turbulence;
+        self
+    }
+
+    /// Higher sharpness gives thinner veins.
+    pub fn with_sharpness(mut self, sharpness: f64) -> Self {
+        self.sharpness = sharpness;
+        self
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let phase = self.frequency * p.0[0] + self.turbulence * self.noise.turb(p, 7);
+        let vein = (1.0 - f64::abs(f64::sin(phase))).powf(self.sharpness);
+        lerp(self.base, self.vein, vein)
+    }
+}
+
+/// Running bond bricks on the `u` and `v` texture coordinates, with every other row shifted by
+/// half a brick. Combine with `TriplanarTexture` for objects without texture coordinates.
+#[derive(Clone)]
+pub struct BrickTexture {
+    noise: Perlin,
+    brick: Color,
+    mortar: Color,
+    width: f64,
+    height: f64,
+    mortar_width: f64,
+    variation: f64,
+}
+
+impl BrickTexture {
+    pub fn new(noise: Perlin, brick: Color, mortar: Color) -> Self {
+        Self {
+            noise,
+            brick,
+            mortar,
+            width: 0.25,
+            height: 0.08,
+            mortar_width: 0.01,
+            variation: 0.3,
+        }
+    }
+
+    /// Sets the size of a brick including its share of mortar, in texture coordinates.
+    pub fn with_size(mut self, width: f64, height: f64) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_mortar_width(mut self, mortar_width: f64) -> Self {
+        self.mortar_width = mortar_width;
+        self
+    }
+
+    /// Sets how much the brightness of each brick varies, from 0 for identical bricks to 1.
+    pub fn with_variation(mut self, variation: f64) -> Self {
+        self.variation = variation;
+        self
+    }
+
+    /// Returns the row and column of the brick at `(u, v)`, or `None` for mortar.
+    fn brick_at(&self, u: f64, v: f64) -> Option<(f64, f64)> {
+        let y = v / self.height;
+        let row = y.floor();
+        let x = u / self.width + if row.rem_euclid(2.0) == 1.0 { 0.5 } else { 0.0 };
+        let column = x.floor();
+
+        let half_mortar_x = 0.5 * self.mortar_width / self.width;
+        let half_mortar_y = 0.5 * self.mortar_width / self.height;
+        let in_mortar = |f: f64, half: f64| f < half || f > 1.0 - half;
+        if in_mortar(x - column, half_mortar_x) || in_mortar(y - row, half_mortar_y) {
+            None
+        } else {
+            Some((row, column))
+        }
+    }
+}
+
+impl Texture for BrickTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        let (row, column) = match self.brick_at(u, v) {
+            Some(brick) => brick,
+            None => return self.mortar,
+        };
+
+        // Perlin noise is zero on the lattice, so look it up off the integer points.
+        let brick_noise = self
+            .noise
+            .noise(&Point::new(column + 0.37, row + 0.61, 0.5));
+        let surface = self.noise.turb(&Point(20.0 * p.0), 3);
+        (1.0 + self.variation * brick_noise - 0.2 * surface).max(0.0) * self.brick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bricks_and_mortar() {
+        let bricks = BrickTexture::new(
+            Perlin::with_rng(&mut rand::thread_rng()),
+            Color::new(0.6, 0.2, 0.1),
+            Color::new(0.8, 0.8, 0.8),
+        )
+        .with_size(1.0, 0.5)
+        .with_mortar_width(0.1);
+        assert_eq!(bricks.brick_at(0.5, 0.25), Some((0.0, 0.0)));
+        assert_eq!(bricks.brick_at(0.02, 0.25), None);
+        assert_eq!(bricks.brick_at(0.5, 0.48), None);
+        // The second row is shifted by half a brick.
+        assert_eq!(bricks.brick_at(0.02, 0.75), Some((1.0, 0.0)));
+        assert_eq!(bricks.brick_at(0.52, 0.75), None);
+    }
+}
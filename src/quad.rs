@@ -0,0 +1,82 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::material::Material;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// A parallelogram with corner `q` and edges `u` and `v`. The texture coordinates run from 0 to
+/// 1 along the two edges.
+pub struct Quad {
+    q: Point,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    /// `n / (n · n)` for the unnormalized normal `n = u × v`, which turns offsets in the plane
+    /// into coordinates along the edges.
+    w: Vec3,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl Quad {
+    pub fn new(q: Point, u: Vec3, v: Vec3, mat: impl Material + Sync + Send + 'static) -> Quad {
+        let n = u.cross(&v);
+        Quad {
+            q,
+            u,
+            v,
+            normal: n.unit_vector(),
+            w: n / n.dot(&n),
+            mat: Box::new(mat),
+        }
+    }
+}
+
+impl Hit for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(r.direction());
+        if f64::abs(denom) < 1e-12 {
+            return None;
+        }
+        let t = self.normal.dot(&(self.q - *r.origin())) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let planar = r.at(t) - self.q;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let mut rec = HitRecord::new(r, self.normal, t, alpha, beta, self.mat.as_ref());
+        (rec.dpdu, rec.dpdv) = (self.u, self.v);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AABB> {
+        let corners = [
+            self.q,
+            self.q + self.u,
+            self.q + self.v,
+            self.q + self.u + self.v,
+        ];
+        let axis =
+            |i: usize, f: fn(f64, f64) -> f64| corners.iter().map(|c| c.0[i]).reduce(f).unwrap();
+        // Pad the box so it does not have zero thickness for axis-aligned quads.
+        let padding = 0.0001;
+        Some(AABB::new(
+            Point::new(
+                axis(0, f64::min) - padding,
+                axis(1, f64::min) - padding,
+                axis(2, f64::min) - padding,
+            ),
+            Point::new(
+                axis(0, f64::max) + padding,
+                axis(1, f64::max) + padding,
+                axis(2, f64::max) + padding,
+            ),
+        ))
+    }
+}
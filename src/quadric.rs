@@ -0,0 +1,536 @@
+//! Analytic quadric surfaces besides `Sphere`, all standing on the xz plane around the y axis
+//! through their center. Like spheres, they can move linearly over a time interval, and can be
+//! swept around the axis by less than a full turn.
+
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::material::Material;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+
+/// The position of a shape's center over time, as for `Sphere::new_moving`.
+#[derive(Clone, Copy)]
+struct Motion {
+    center_start: Point,
+    center_end: Point,
+    time_start: f64,
+    time_end: f64,
+}
+
+impl Motion {
+    fn fixed(center: Point) -> Motion {
+        Motion {
+            center_start: center,
+            center_end: center,
+            time_start: 0.0,
+            time_end: 1.0,
+        }
+    }
+
+    fn center(&self, time: f64) -> Point {
+        self.center_start
+            + ((time - self.time_start) / (self.time_end - self.time_start))
+                * (self.center_end - self.center_start)
+    }
+
+    /// Moves `r` into the frame of the shape, where its center is at the origin.
+    fn local_ray(&self, r: &Ray) -> Ray {
+        Ray::new(
+            *r.origin() - self.center(r.time()).0,
+            *r.direction(),
+            r.time(),
+        )
+    }
+
+    /// Moves a hit found with `local_ray` back into the world. Object space is where the shape
+    /// is at the start of its motion.
+    fn place(&self, rec: &mut HitRecord, time: f64) {
+        rec.object_p = rec.p + self.center_start.0;
+        rec.p = rec.p + self.center(time).0;
+    }
+
+    /// Returns a box around the local `minimum` and `maximum` over the whole motion.
+    fn bounds(&self, minimum: Vec3, maximum: Vec3, start_time: f64, end_time: f64) -> AABB {
+        let at = |time: f64| {
+            let center = self.center(time);
+            AABB::new(center + minimum, center + maximum)
+        };
+        AABB::surrounding_box(&at(start_time), &at(end_time))
+    }
+}
+
+/// Returns the angle of the local point `p` around the y axis, in `[0, 2 pi)`.
+fn azimuth(p: &Point) -> f64 {
+    let phi = f64::atan2(p.0[2], p.0[0]);
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+/// Returns the direction away from the local y axis through `p`, scaled to `length`, or along x
+/// for points on the axis where any direction is as good as another.
+fn radial(p: &Point, distance: f64, length: f64) -> Vec3 {
+    if distance < 1e-12 {
+        return Vec3::new(length, 0.0, 0.0);
+    }
+    Vec3::new(p.0[0], 0.0, p.0[2]) * (length / distance)
+}
+
+/// Returns the roots of `a t^2 + 2 half_b t + c` in increasing order. When `a` vanishes, as for
+/// rays parallel to the side of a cone, the single root of the linear equation is returned
+/// twice.
+fn solve_quadratic(a: f64, half_b: f64, c: f64) -> Option<[f64; 2]> {
+    if f64::abs(a) < 1e-12 {
+        if f64::abs(half_b) < 1e-12 {
+            return None;
+        }
+        let t = -c / (2.0 * half_b);
+        return Some([t, t]);
+    }
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrtd = f64::sqrt(discriminant);
+    let (t0, t1) = ((-half_b - sqrtd) / a, (-half_b + sqrtd) / a);
+    Some(if t0 <= t1 { [t0, t1] } else { [t1, t0] })
+}
+
+/// Intersects a horizontal disk at height `y` around the local y axis, with radii between
+/// `inner_radius` and `radius`, swept by `phi_max`. Returns the ray parameter, the local hit
+/// point and its distance from the axis.
+fn hit_disk(
+    r: &Ray,
+    y: f64,
+    inner_radius: f64,
+    radius: f64,
+    phi_max: f64,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, Point, f64)> {
+    let dy = r.direction()[1];
+    if f64::abs(dy) < 1e-12 {
+        return None;
+    }
+    let t = (y - r.origin().0[1]) / dy;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    let p = r.at(t);
+    let distance = f64::sqrt(p.0[0] * p.0[0] + p.0[2] * p.0[2]);
+    if distance > radius || distance < inner_radius || azimuth(&p) > phi_max {
+        return None;
+    }
+    Some((t, p, distance))
+}
+
+/// The derivative of a point swept around the y axis with respect to `u = phi / phi_max`.
+fn sweep_partial(p: &Point, phi_max: f64) -> Vec3 {
+    Vec3::new(-phi_max * p.0[2], 0.0, phi_max * p.0[0])
+}
+
+/// A cylinder of `radius` from its center up to `height`, open unless given caps.
+pub struct Cylinder {
+    motion: Motion,
+    radius: f64,
+    height: f64,
+    phi_max: f64,
+    capped: bool,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl Cylinder {
+    pub fn new(
+        center: Point,
+        radius: f64,
+        height: f64,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Cylinder {
+        Cylinder {
+            motion: Motion::fixed(center),
+            radius,
+            height,
+            phi_max: 2.0 * PI,
+            capped: false,
+            mat: Box::new(mat),
+        }
+    }
+
+    pub fn new_moving(
+        center_start: Point,
+        center_end: Point,
+        time_start: f64,
+        time_end: f64,
+        radius: f64,
+        height: f64,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Cylinder {
+        Cylinder {
+            motion: Motion {
+                center_start,
+                center_end,
+                time_start,
+                time_end,
+            },
+            ..Cylinder::new(center_start, radius, height, mat)
+        }
+    }
+
+    /// Sweeps the cylinder by `degrees` around its axis instead of a full turn.
+    pub fn with_phi_max(mut self, degrees: f64) -> Cylinder {
+        self.phi_max = degrees.clamp(0.0, 360.0).to_radians();
+        self
+    }
+
+    /// Closes the cylinder with disks at the bottom and top.
+    pub fn with_caps(mut self, capped: bool) -> Cylinder {
+        self.capped = capped;
+        self
+    }
+}
+
+impl Hit for Cylinder {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let local = self.motion.local_ray(r);
+        let (o, d) = (local.origin().0, *local.direction());
+
+        let mut closest: Option<HitRecord> = None;
+        let mut t_max = t_max;
+
+        let a = d[0] * d[0] + d[2] * d[2];
+        let half_b = o[0] * d[0] + o[2] * d[2];
+        let c = o[0] * o[0] + o[2] * o[2] - self.radius * self.radius;
+        if let Some(roots) = solve_quadratic(a, half_b, c) {
+            for t in roots {
+                if t < t_min || t > t_max {
+                    continue;
+                }
+                let p = local.at(t);
+                let phi = azimuth(&p);
+                if p.0[1] < 0.0 || p.0[1] > self.height || phi > self.phi_max {
+                    continue;
+                }
+
+                let outward_normal = Vec3::new(p.0[0], 0.0, p.0[2]) / self.radius;
+                let (u, v) = (phi / self.phi_max, p.0[1] / self.height);
+                let mut rec = HitRecord::new(&local, outward_normal, t, u, v, self.mat.as_ref());
+                rec.dpdu = sweep_partial(&p, self.phi_max);
+                rec.dpdv = Vec3::new(0.0, self.height, 0.0);
+                t_max = t;
+                closest = Some(rec);
+                break;
+            }
+        }
+
+        if self.capped {
+            for (y, normal) in [(0.0, -1.0), (self.height, 1.0)] {
+                if let Some((t, p, distance)) =
+                    hit_disk(&local, y, 0.0, self.radius, self.phi_max, t_min, t_max)
+                {
+                    let (u, v) = (azimuth(&p) / self.phi_max, distance / self.radius);
+                    let outward_normal = Vec3::new(0.0, normal, 0.0);
+                    let mut rec =
+                        HitRecord::new(&local, outward_normal, t, u, v, self.mat.as_ref());
+                    rec.dpdu = sweep_partial(&p, self.phi_max);
+                    rec.dpdv = radial(&p, distance, self.radius);
+                    t_max = t;
+                    closest = Some(rec);
+                }
+            }
+        }
+
+        closest.map(|mut rec| {
+            self.motion.place(&mut rec, r.time());
+            rec
+        })
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
+        Some(self.motion.bounds(
+            Vec3::new(-self.radius, 0.0, -self.radius),
+            Vec3::new(self.radius, self.height, self.radius),
+            start_time,
+            end_time,
+        ))
+    }
+}
+
+/// A cone with a base of `radius` at its center and its apex `height` above, open at the base
+/// unless given a cap.
+pub struct Cone {
+    motion: Motion,
+    radius: f64,
+    height: f64,
+    phi_max: f64,
+    capped: bool,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl Cone {
+    pub fn new(
+        center: Point,
+        radius: f64,
+        height: f64,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Cone {
+        Cone {
+            motion: Motion::fixed(center),
+            radius,
+            height,
+            phi_max: 2.0 * PI,
+            capped: false,
+            mat: Box::new(mat),
+        }
+    }
+
+    pub fn new_moving(
+        center_start: Point,
+        center_end: Point,
+        time_start: f64,
+        time_end: f64,
+        radius: f64,
+        height: f64,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Cone {
+        Cone {
+            motion: Motion {
+                center_start,
+                center_end,
+                time_start,
+                time_end,
+            },
+            ..Cone::new(center_start, radius, height, mat)
+        }
+    }
+
+    /// Sweeps the cone by `degrees` around its axis instead of a full turn.
+    pub fn with_phi_max(mut self, degrees: f64) -> Cone {
+        self.phi_max = degrees.clamp(0.0, 360.0).to_radians();
+        self
+    }
+
+    /// Closes the base of the cone with a disk.
+    pub fn with_caps(mut self, capped: bool) -> Cone {
+        self.capped = capped;
+        self
+    }
+}
+
+impl Hit for Cone {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let local = self.motion.local_ray(r);
+        let (o, d) = (local.origin().0, *local.direction());
+
+        let mut closest: Option<HitRecord> = None;
+        let mut t_max = t_max;
+
+        // x^2 + z^2 = (k (height - y))^2 with the slope k of the side.
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let h = self.height - o[1];
+        let a = d[0] * d[0] + d[2] * d[2] - k2 * d[1] * d[1];
+        let half_b = o[0] * d[0] + o[2] * d[2] + k2 * h * d[1];
+        let c = o[0] * o[0] + o[2] * o[2] - k2 * h * h;
+        if let Some(roots) = solve_quadratic(a, half_b, c) {
+            for t in roots {
+                if t < t_min || t > t_max {
+                    continue;
+                }
+                let p = local.at(t);
+                let phi = azimuth(&p);
+                if p.0[1] < 0.0 || p.0[1] > self.height || phi > self.phi_max {
+                    continue;
+                }
+
+                let outward_normal =
+                    Vec3::new(p.0[0], k2 * (self.height - p.0[1]), p.0[2]).unit_vector();
+                let (u, v) = (phi / self.phi_max, p.0[1] / self.height);
+                let mut rec = HitRecord::new(&local, outward_normal, t, u, v, self.mat.as_ref());
+                rec.dpdu = sweep_partial(&p, self.phi_max);
+                rec.dpdv = Vec3::new(
+                    -self.radius * f64::cos(phi),
+                    self.height,
+                    -self.radius * f64::sin(phi),
+                );
+                t_max = t;
+                closest = Some(rec);
+                break;
+            }
+        }
+
+        if self.capped {
+            if let Some((t, p, distance)) =
+                hit_disk(&local, 0.0, 0.0, self.radius, self.phi_max, t_min, t_max)
+            {
+                let (u, v) = (azimuth(&p) / self.phi_max, distance / self.radius);
+                let outward_normal = Vec3::new(0.0, -1.0, 0.0);
+                let mut rec = HitRecord::new(&local, outward_normal, t, u, v, self.mat.as_ref());
+                rec.dpdu = sweep_partial(&p, self.phi_max);
+                rec.dpdv = radial(&p, distance, self.radius);
+                closest = Some(rec);
+            }
+        }
+
+        closest.map(|mut rec| {
+            self.motion.place(&mut rec, r.time());
+            rec
+        })
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
+        Some(self.motion.bounds(
+            Vec3::new(-self.radius, 0.0, -self.radius),
+            Vec3::new(self.radius, self.height, self.radius),
+            start_time,
+            end_time,
+        ))
+    }
+}
+
+/// A flat disk of `radius` facing up, with a hole of `inner_radius` for an annulus. `u` runs
+/// around the disk and `v` from the outer rim inwards.
+pub struct Disk {
+    motion: Motion,
+    inner_radius: f64,
+    radius: f64,
+    phi_max: f64,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl Disk {
+    pub fn new(center: Point, radius: f64, mat: impl Material + Sync + Send + 'static) -> Disk {
+        Disk::annulus(center, 0.0, radius, mat)
+    }
+
+    pub fn annulus(
+        center: Point,
+        inner_radius: f64,
+        radius: f64,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Disk {
+        Disk {
+            motion: Motion::fixed(center),
+            inner_radius,
+            radius,
+            phi_max: 2.0 * PI,
+            mat: Box::new(mat),
+        }
+    }
+
+    pub fn new_moving(
+        center_start: Point,
+        center_end: Point,
+        time_start: f64,
+        time_end: f64,
+        inner_radius: f64,
+        radius: f64,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Disk {
+        Disk {
+            motion: Motion {
+                center_start,
+                center_end,
+                time_start,
+                time_end,
+            },
+            ..Disk::annulus(center_start, inner_radius, radius, mat)
+        }
+    }
+
+    /// Sweeps the disk by `degrees` around its center instead of a full turn.
+    pub fn with_phi_max(mut self, degrees: f64) -> Disk {
+        self.phi_max = degrees.clamp(0.0, 360.0).to_radians();
+        self
+    }
+}
+
+impl Hit for Disk {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let local = self.motion.local_ray(r);
+        let (t, p, distance) = hit_disk(
+            &local,
+            0.0,
+            self.inner_radius,
+            self.radius,
+            self.phi_max,
+            t_min,
+            t_max,
+        )?;
+
+        let width = self.radius - self.inner_radius;
+        let (u, v) = (azimuth(&p) / self.phi_max, (self.radius - distance) / width);
+        let outward_normal = Vec3::new(0.0, 1.0, 0.0);
+        let mut rec = HitRecord::new(&local, outward_normal, t, u, v, self.mat.as_ref());
+        rec.dpdu = sweep_partial(&p, self.phi_max);
+        rec.dpdv = radial(&p, distance, -width);
+        self.motion.place(&mut rec, r.time());
+        Some(rec)
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
+        // Pad the box so it does not have zero thickness.
+        let padding = 0.0001;
+        Some(self.motion.bounds(
+            Vec3::new(-self.radius, -padding, -self.radius),
+            Vec3::new(self.radius, padding, self.radius),
+            start_time,
+            end_time,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    fn gray() -> Lambertian {
+        Lambertian::from_color(Color::new(0.5, 0.5, 0.5))
+    }
+
+    #[test]
+    fn test_quadrics() {
+        let center = Point::new(0.0, 0.0, 0.0);
+        let side = Ray::new(Point::new(5.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        let down = Ray::new(Point::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+
+        let cylinder = Cylinder::new(center, 1.0, 2.0, gray());
+        let rec = cylinder.hit(&side, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 4.0) < 1e-9);
+        assert_eq!(rec.normal[0], 1.0);
+        // Open cylinders can be seen through along their axis.
+        assert!(cylinder.hit(&down, 0.001, f64::INFINITY).is_none());
+        let capped = Cylinder::new(center, 1.0, 2.0, gray()).with_caps(true);
+        let rec = capped.hit(&down, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 3.0) < 1e-9);
+        // A cap hit right on the axis still gets a usable tangent.
+        let axis = Ray::new(Point::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = capped.hit(&axis, 0.001, f64::INFINITY).unwrap();
+        assert!(rec.dpdv.length().is_finite() && rec.dpdv.length() > 0.0);
+
+        // Half a cylinder swept from +x towards +z has no near wall when seen from -z, so the
+        // ray hits the inside of the far wall.
+        let half = Cylinder::new(center, 1.0, 2.0, gray()).with_phi_max(180.0);
+        let behind = Ray::new(Point::new(0.0, 1.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let rec = half.hit(&behind, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.p.0[2] - 1.0) < 1e-9);
+
+        let cone = Cone::new(center, 1.0, 2.0, gray());
+        let rec = cone.hit(&side, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.p.0[0] - 0.5) < 1e-9);
+        // A ray parallel to one side of the cone hits the opposite side.
+        let parallel = Ray::new(Point::new(-1.5, 2.0, 0.0), Vec3::new(0.5, -1.0, 0.0), 0.0);
+        let rec = cone.hit(&parallel, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.5) < 1e-9);
+
+        let annulus = Disk::annulus(center, 0.4, 1.0, gray());
+        assert!(annulus.hit(&down, 0.001, f64::INFINITY).is_some());
+        let hole = Ray::new(Point::new(0.2, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(annulus.hit(&hole, 0.001, f64::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,116 @@
+//! Real roots of cubic and quartic polynomials, for intersecting rays with quartic surfaces such
+//! as the torus.
+
+/// Returns the real roots of `x^3 + a x^2 + b x + c`, unsorted.
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let shift = a / 3.0;
+
+    if r * r < q * q * q {
+        // Three real roots.
+        let theta = f64::acos(r / f64::sqrt(q * q * q));
+        let scale = -2.0 * f64::sqrt(q);
+        (0..3)
+            .map(|k| {
+                scale * f64::cos((theta + 2.0 * std::f64::consts::PI * k as f64) / 3.0) - shift
+            })
+            .collect()
+    } else {
+        let big_a = -r.signum() * f64::cbrt(f64::abs(r) + f64::sqrt(r * r - q * q * q));
+        let big_b = if big_a == 0.0 { 0.0 } else { q / big_a };
+        vec![big_a + big_b - shift]
+    }
+}
+
+/// Returns the real roots of `a x^4 + b x^3 + c x^2 + d x + e` in increasing order, using
+/// Ferrari's method followed by a few Newton steps on the original polynomial to recover the
+/// precision lost in the reduction.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if f64::abs(a) < 1e-12 {
+        return Vec::new();
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Substitute x = y - b / 4 for the depressed quartic y^4 + p y^2 + q y + r.
+    let shift = b / 4.0;
+    let p = c - 6.0 * shift * shift;
+    let q = d - 2.0 * c * shift + 8.0 * shift * shift * shift;
+    let r = e - d * shift + c * shift * shift - 3.0 * shift * shift * shift * shift;
+
+    let mut roots = Vec::with_capacity(4);
+    let mut push_quadratic = |b: f64, c: f64| {
+        let discriminant = b * b - 4.0 * c;
+        if discriminant >= 0.0 {
+            let sqrtd = f64::sqrt(discriminant);
+            roots.extend([(-b - sqrtd) / 2.0, (-b + sqrtd) / 2.0]);
+        }
+    };
+
+    if f64::abs(q) < 1e-12 {
+        // Biquadratic: solve for y^2.
+        let discriminant = p * p - 4.0 * r;
+        if discriminant >= 0.0 {
+            let sqrtd = f64::sqrt(discriminant);
+            for y2 in [(-p - sqrtd) / 2.0, (-p + sqrtd) / 2.0] {
+                if y2 >= 0.0 {
+                    push_quadratic(0.0, -y2);
+                }
+            }
+        }
+    } else {
+        // Complete the square with the largest root of the resolvent cubic
+        // 8 m^3 + 8 p m^2 + (2 p^2 - 8 r) m - q^2, which is positive when q is not zero.
+        let m = solve_cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return Vec::new();
+        }
+        let s = f64::sqrt(2.0 * m);
+        push_quadratic(-s, p / 2.0 + m + q / (2.0 * s));
+        push_quadratic(s, p / 2.0 + m - q / (2.0 * s));
+    }
+
+    let f = |x: f64| (((x + b) * x + c) * x + d) * x + e;
+    let df = |x: f64| ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+    let mut roots: Vec<f64> = roots
+        .into_iter()
+        .map(|y| {
+            let mut x = y - shift;
+            for _ in 0..2 {
+                let slope = df(x);
+                if f64::abs(slope) > 1e-12 {
+                    x -= f(x) / slope;
+                }
+            }
+            x
+        })
+        .collect();
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_quartic() {
+        // (x - 1)(x + 2)(x - 3)(x - 0.5) expanded.
+        let roots = solve_quartic(1.0, -2.5, -4.0, 8.5, -3.0);
+        let expected = [-2.0, 0.5, 1.0, 3.0];
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!(f64::abs(root - expected) < 1e-9);
+        }
+
+        // (x^2 - 4)(x^2 + 1) has only two real roots.
+        let roots = solve_quartic(2.0, 0.0, -6.0, 0.0, -8.0);
+        assert_eq!(roots.len(), 2);
+        assert!(f64::abs(roots[0] + 2.0) < 1e-9 && f64::abs(roots[1] - 2.0) < 1e-9);
+
+        // x^4 + 1 has none.
+        assert!(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0).is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::color::Color;
+
+/// How a `ColorRamp` blends between two neighboring stops.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RampInterpolation {
+    Linear,
+    /// Keeps the color of the lower stop until the next one, giving hard bands.
+    Constant,
+    /// Eases in and out of each stop with a smoothstep curve, hiding the kinks at the stops.
+    Smoothstep,
+}
+
+/// Maps a scalar to a color by interpolating between colored stops.
+#[derive(Clone)]
+pub struct ColorRamp {
+    stops: Vec<(f64, Color)>,
+    interpolation: RampInterpolation,
+}
+
+impl ColorRamp {
+    /// Creates a ramp from `(position, color)` stops, in any order. Values before the first or
+    /// after the last stop take the color of that stop.
+    pub fn new(mut stops: Vec<(f64, Color)>) -> ColorRamp {
+        assert!(!stops.is_empty(), "A color ramp needs at least one stop.");
+        assert!(
+            stops.iter().all(|(position, _)| !position.is_nan()),
+            "Color ramp stops need a position."
+        );
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ColorRamp {
+            stops,
+            interpolation: RampInterpolation::Linear,
+        }
+    }
+
+    /// A ramp from `start` at 0 to `end` at 1.
+    pub fn gradient(start: Color, end: Color) -> ColorRamp {
+        ColorRamp::new(vec![(0.0, start), (1.0, end)])
+    }
+
+    pub fn with_interpolation(mut self, interpolation: RampInterpolation) -> ColorRamp {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn eval(&self, t: f64) -> Color {
+        let next = self.stops.partition_point(|(position, _)| *position <= t);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        if next == self.stops.len() {
+            return self.stops[next - 1].1;
+        }
+
+        let (p0, c0) = self.stops[next - 1];
+        let (p1, c1) = self.stops[next];
+        let s = (t - p0) / (p1 - p0);
+        let s = match self.interpolation {
+            RampInterpolation::Linear => s,
+            RampInterpolation::Constant => 0.0,
+            RampInterpolation::Smoothstep => s * s * (3.0 - 2.0 * s),
+        };
+        (1.0 - s) * c0 + s * c1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolation() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let ramp = ColorRamp::new(vec![(1.0, white), (0.0, black)]);
+        assert_eq!(ramp.eval(-1.0).0[0], 0.0);
+        assert_eq!(ramp.eval(0.25).0[0], 0.25);
+        assert_eq!(ramp.eval(2.0).0[0], 1.0);
+
+        let constant = ramp.clone().with_interpolation(RampInterpolation::Constant);
+        assert_eq!(constant.eval(0.75).0[0], 0.0);
+        assert_eq!(constant.eval(1.0).0[0], 1.0);
+
+        let smooth = ramp.with_interpolation(RampInterpolation::Smoothstep);
+        assert_eq!(smooth.eval(0.25).0[0], 0.15625);
+        assert_eq!(smooth.eval(0.5).0[0], 0.5);
+    }
+}
@@ -1,15 +1,45 @@
 use crate::point::Point;
 use crate::vec3::Vec3;
 
+/// Two auxiliary rays offset from the main one by a pixel in x and y on the image, used to
+/// estimate the footprint of the pixel on the surfaces it hits.
+#[derive(Clone, Copy)]
+pub struct RayDifferential {
+    pub rx_origin: Point,
+    pub rx_direction: Vec3,
+    pub ry_origin: Point,
+    pub ry_direction: Vec3,
+}
+
 pub struct Ray {
     orig: Point,
     dir: Vec3,
     time: f64,
+    differential: Option<RayDifferential>,
 }
 
 impl Ray {
     pub fn new(orig: Point, dir: Vec3, time: f64) -> Ray {
-        Ray { orig, dir, time }
+        Ray {
+            orig,
+            dir,
+            time,
+            differential: None,
+        }
+    }
+
+    pub fn with_differential(
+        orig: Point,
+        dir: Vec3,
+        time: f64,
+        differential: RayDifferential,
+    ) -> Ray {
+        Ray {
+            orig,
+            dir,
+            time,
+            differential: Some(differential),
+        }
     }
 
     pub fn origin(&self) -> &Point {
@@ -24,6 +54,10 @@ impl Ray {
         self.time
     }
 
+    pub fn differential(&self) -> Option<&RayDifferential> {
+        self.differential.as_ref()
+    }
+
     pub fn at(&self, t: f64) -> Point {
         self.orig + t * self.dir
     }
@@ -0,0 +1,431 @@
+//! Surfaces given implicitly by signed distance functions, which are negative inside and
+//! positive outside, and traced by sphere tracing: the ray repeatedly steps forward by the
+//! distance to the surface until it gets close enough.
+
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::material::Material;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+pub trait Sdf {
+    /// Returns the signed distance from `p` to the surface. It may underestimate the true
+    /// distance, which only slows tracing down, but must never overestimate it.
+    fn distance(&self, p: &Point) -> f64;
+
+    /// Returns a box that contains the whole surface.
+    fn bounds(&self) -> AABB;
+}
+
+type SdfRef = Box<dyn Sdf + Sync + Send>;
+
+fn max_axis(v: Vec3) -> Vec3 {
+    Vec3::new(
+        f64::max(v[0], 0.0),
+        f64::max(v[1], 0.0),
+        f64::max(v[2], 0.0),
+    )
+}
+
+fn abs_axis(v: Vec3) -> Vec3 {
+    Vec3::new(f64::abs(v[0]), f64::abs(v[1]), f64::abs(v[2]))
+}
+
+fn box_around(center: Point, half_size: Vec3) -> AABB {
+    AABB::new(center - half_size, center + half_size)
+}
+
+pub struct SdfSphere {
+    center: Point,
+    radius: f64,
+}
+
+impl SdfSphere {
+    pub fn new(center: Point, radius: f64) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: &Point) -> f64 {
+        (*p - self.center).length() - self.radius
+    }
+
+    fn bounds(&self) -> AABB {
+        box_around(
+            self.center,
+            Vec3::new(self.radius, self.radius, self.radius),
+        )
+    }
+}
+
+/// An axis-aligned box reaching `half_size` from its center, with edges rounded by `radius`
+/// within that size.
+pub struct SdfBox {
+    center: Point,
+    half_size: Vec3,
+    radius: f64,
+}
+
+impl SdfBox {
+    pub fn new(center: Point, half_size: Vec3) -> Self {
+        Self::rounded(center, half_size, 0.0)
+    }
+
+    pub fn rounded(center: Point, half_size: Vec3, radius: f64) -> Self {
+        Self {
+            center,
+            half_size,
+            radius,
+        }
+    }
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, p: &Point) -> f64 {
+        let rounding = Vec3::new(self.radius, self.radius, self.radius);
+        let q = abs_axis(*p - self.center) - (self.half_size - rounding);
+        let inside = f64::min(f64::max(q[0], f64::max(q[1], q[2])), 0.0);
+        max_axis(q).length() + inside - self.radius
+    }
+
+    fn bounds(&self) -> AABB {
+        box_around(self.center, self.half_size)
+    }
+}
+
+/// The points within `radius` of the segment from `a` to `b`.
+pub struct Capsule {
+    a: Point,
+    b: Point,
+    radius: f64,
+}
+
+impl Capsule {
+    pub fn new(a: Point, b: Point, radius: f64) -> Self {
+        Self { a, b, radius }
+    }
+}
+
+impl Sdf for Capsule {
+    fn distance(&self, p: &Point) -> f64 {
+        let (pa, ba) = (*p - self.a, self.b - self.a);
+        let h = (pa.dot(&ba) / ba.length_squared()).clamp(0.0, 1.0);
+        (pa - h * ba).length() - self.radius
+    }
+
+    fn bounds(&self) -> AABB {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        AABB::surrounding_box(&box_around(self.a, r), &box_around(self.b, r))
+    }
+}
+
+/// A ring in the xz plane, like `Torus`.
+pub struct SdfTorus {
+    center: Point,
+    major_radius: f64,
+    minor_radius: f64,
+}
+
+impl SdfTorus {
+    pub fn new(center: Point, major_radius: f64, minor_radius: f64) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, p: &Point) -> f64 {
+        let q = *p - self.center;
+        let radial = f64::sqrt(q[0] * q[0] + q[2] * q[2]) - self.major_radius;
+        f64::sqrt(radial * radial + q[1] * q[1]) - self.minor_radius
+    }
+
+    fn bounds(&self) -> AABB {
+        let extent = self.major_radius + self.minor_radius;
+        box_around(self.center, Vec3::new(extent, self.minor_radius, extent))
+    }
+}
+
+/// A distance function given as a closure, with the box it is bounded by.
+pub struct SdfFn<F> {
+    f: F,
+    bounds: AABB,
+}
+
+impl<F: Fn(&Point) -> f64> SdfFn<F> {
+    pub fn new(bounds: AABB, f: F) -> Self {
+        Self { f, bounds }
+    }
+}
+
+impl<F: Fn(&Point) -> f64> Sdf for SdfFn<F> {
+    fn distance(&self, p: &Point) -> f64 {
+        (self.f)(p)
+    }
+
+    fn bounds(&self) -> AABB {
+        self.bounds.clone()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SdfOp {
+    Union,
+    Intersection,
+    /// The first shape with the second cut out of it.
+    Subtraction,
+}
+
+/// Combines two distance functions, optionally blending them over a distance of `smoothness`
+/// so the shapes melt into each other.
+pub struct SdfCombine {
+    a: SdfRef,
+    b: SdfRef,
+    op: SdfOp,
+    smoothness: f64,
+}
+
+impl SdfCombine {
+    pub fn new(
+        a: impl Sdf + Sync + Send + 'static,
+        b: impl Sdf + Sync + Send + 'static,
+        op: SdfOp,
+    ) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            op,
+            smoothness: 0.0,
+        }
+    }
+
+    pub fn with_smoothness(mut self, smoothness: f64) -> Self {
+        self.smoothness = smoothness;
+        self
+    }
+
+    /// The polynomial smooth minimum, which is never more than `smoothness / 4` below `min`.
+    fn smooth_min(&self, a: f64, b: f64) -> f64 {
+        let k = self.smoothness;
+        if k <= 0.0 {
+            return f64::min(a, b);
+        }
+        let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+        b + (a - b) * h - k * h * (1.0 - h)
+    }
+}
+
+impl Sdf for SdfCombine {
+    fn distance(&self, p: &Point) -> f64 {
+        let (a, b) = (self.a.distance(p), self.b.distance(p));
+        match self.op {
+            SdfOp::Union => self.smooth_min(a, b),
+            SdfOp::Intersection => -self.smooth_min(-a, -b),
+            SdfOp::Subtraction => -self.smooth_min(-a, b),
+        }
+    }
+
+    fn bounds(&self) -> AABB {
+        let (a, b) = (self.a.bounds(), self.b.bounds());
+        match self.op {
+            SdfOp::Union => {
+                let pad = 0.25 * self.smoothness;
+                let joined = AABB::surrounding_box(&a, &b);
+                box_around(
+                    Point(0.5 * (joined.minimum.0 + joined.maximum.0)),
+                    0.5 * (joined.maximum - joined.minimum) + Vec3::new(pad, pad, pad),
+                )
+            }
+            // Intersections and subtractions only ever shrink the shapes.
+            SdfOp::Intersection => AABB::overlap(&a, &b),
+            SdfOp::Subtraction => a,
+        }
+    }
+}
+
+/// Traces the surface of a signed distance function.
+pub struct SdfObject {
+    sdf: SdfRef,
+    bounds: AABB,
+    epsilon: f64,
+    max_steps: usize,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl SdfObject {
+    pub fn new(
+        sdf: impl Sdf + Sync + Send + 'static,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Self {
+        let bounds = sdf.bounds();
+        Self {
+            sdf: Box::new(sdf),
+            bounds,
+            epsilon: 1e-4,
+            max_steps: 256,
+            mat: Box::new(mat),
+        }
+    }
+
+    /// Sets how close to the surface a ray has to get to count as a hit.
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Sets how many steps a ray may take before it is counted as a miss. Fractals and
+    /// distance functions that underestimate a lot need more.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Returns the range of the ray parameter inside the bounds, if any.
+    fn clip(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (mut t0, mut t1) = (t_min, t_max);
+        for i in 0..3 {
+            let inv_d = 1.0 / r.direction()[i];
+            let mut near = (self.bounds.minimum.0[i] - r.origin().0[i]) * inv_d;
+            let mut far = (self.bounds.maximum.0[i] - r.origin().0[i]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut near, &mut far);
+            }
+            t0 = f64::max(t0, near);
+            t1 = f64::min(t1, far);
+        }
+        if t0 > t1 {
+            None
+        } else {
+            Some((t0, t1))
+        }
+    }
+
+    /// The gradient of the distance by central differences on a tetrahedron.
+    fn gradient(&self, p: &Point) -> Vec3 {
+        let h = 0.5 * self.epsilon;
+        [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .fold(Vec3::new(0.0, 0.0, 0.0), |sum, k| {
+            sum + self.sdf.distance(&(*p + h * k)) * k
+        })
+    }
+}
+
+impl Hit for SdfObject {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t_start, t_end) = self.clip(r, t_min, t_max)?;
+        let speed = r.direction().length();
+
+        // Rays leaving the surface from inside, like refracted rays, march on the negated
+        // distance until they reach it from the other side.
+        let side = self.sdf.distance(&r.at(t_start)).signum();
+        let mut t = t_start;
+        for _ in 0..self.max_steps {
+            let distance = side * self.sdf.distance(&r.at(t));
+            if distance < self.epsilon {
+                let outward_normal = self.gradient(&r.at(t));
+                if outward_normal.near_zero() {
+                    return None;
+                }
+                let outward_normal = outward_normal.unit_vector();
+                return Some(HitRecord::new(
+                    r,
+                    outward_normal,
+                    t,
+                    0.0,
+                    0.0,
+                    self.mat.as_ref(),
+                ));
+            }
+            t += distance / speed;
+            if t > t_end {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        let speed = r.direction().length();
+        let mut hits = Vec::new();
+        let mut t_min = t_min;
+        while let Some(rec) = self.hit(r, t_min, t_max) {
+            // A hit is only within epsilon of the surface, so searching again from just past it
+            // would find the same surface. March through it until the ray is clearly on the
+            // other side first, which is inside if the ray entered the surface.
+            let side = if rec.front_face { -1.0 } else { 1.0 };
+            let mut t = rec.t;
+            let mut crossed = false;
+            for _ in 0..self.max_steps {
+                let distance = side * self.sdf.distance(&r.at(t));
+                if distance >= self.epsilon {
+                    crossed = true;
+                    break;
+                }
+                // Aim past epsilon, or the steps shrink without ever getting there.
+                t += (2.0 * self.epsilon - distance) / speed;
+            }
+            hits.push(rec);
+            if !crossed || t > t_max {
+                break;
+            }
+            t_min = t;
+        }
+        hits
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AABB> {
+        Some(self.bounds.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_sphere_tracing() {
+        let blob = SdfCombine::new(
+            SdfSphere::new(Point::new(-0.5, 0.0, 0.0), 0.5),
+            SdfSphere::new(Point::new(0.5, 0.0, 0.0), 0.5),
+            SdfOp::Union,
+        )
+        .with_smoothness(0.4);
+        // The smooth union fills in the waist where the two spheres touch.
+        assert!(blob.distance(&Point::new(0.0, 0.0, 0.0)) < -0.09);
+        assert!(blob.distance(&Point::new(0.0, 0.1, 0.0)) < 0.0);
+
+        let object = SdfObject::new(blob, Lambertian::from_color(Color::new(0.5, 0.5, 0.5)));
+        let r = Ray::new(Point::new(-0.5, 5.0, 0.0), Vec3::new(0.0, -2.0, 0.0), 0.0);
+        let rec = object.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.25) < 1e-3);
+        assert!(f64::abs(rec.normal[1] - 1.0) < 1e-3);
+
+        // Both sides of the lobe the ray passes through, the way CSG needs them.
+        let hits = object.hit_all(&r, 0.001, f64::INFINITY);
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].front_face && !hits[1].front_face);
+        assert!(f64::abs(hits[1].t - 2.75) < 1e-3);
+
+        let cut = SdfCombine::new(
+            SdfBox::new(Point::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+            Capsule::new(Point::new(0.0, -2.0, 0.0), Point::new(0.0, 2.0, 0.0), 0.5),
+            SdfOp::Subtraction,
+        );
+        let object = SdfObject::new(cut, Lambertian::from_color(Color::new(0.5, 0.5, 0.5)));
+        let r = Ray::new(Point::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(object.hit(&r, 0.001, f64::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::color::Color;
+use crate::light::DirectionalLight;
+use crate::vec3::Vec3;
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::sync::Arc;
+
+/// Angular radius of the sun as seen from the earth, in radians.
+const SUN_ANGULAR_RADIUS: f64 = 0.00465;
+/// Luminance of the sun's disk outside the atmosphere, in kcd/m^2 like the sky model.
+const SUN_LUMINANCE: f64 = 1.6e6;
+/// Representative wavelengths of the red, green and blue channels, in micrometers.
+const RGB_WAVELENGTHS: [f64; 3] = [0.65, 0.57, 0.475];
+
+pub enum Sky {
+    /// The white to light blue gradient from the book.
+    Gradient,
+    Uniform(Color),
+    Preetham(PreethamSky),
+}
+
+impl Sky {
+    /// Returns the radiance arriving along the unit vector `direction`. The sun's disk is only
+    /// included if `include_sun` is set, since paths that sample the sun as a light mustn't
+    /// count it twice.
+    pub fn value(&self, direction: &Vec3, include_sun: bool) -> Color {
+        match self {
+            Sky::Gradient => {
+                let t = 0.5 * (direction[1] + 1.0);
+                (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+            }
+            Sky::Uniform(color) => *color,
+            Sky::Preetham(sky) => {
+                let sun = if include_sun { sky.sun.as_ref() } else { None };
+                match sun.and_then(|sun| sun.radiance_towards(direction)) {
+                    Some(radiance) => radiance,
+                    None => sky.value(direction),
+                }
+            }
+        }
+    }
+
+    /// The sun as a light that can be sampled with shadow rays, if it is above the horizon.
+    pub fn sun(&self) -> Option<Arc<DirectionalLight>> {
+        match self {
+            Sky::Gradient | Sky::Uniform(_) => None,
+            Sky::Preetham(sky) => sky.sun.clone(),
+        }
+    }
+}
+
+/// Coefficients of the Perez sky luminance distribution.
+struct Perez([f64; 5]);
+
+impl Perez {
+    fn new(turbidity: f64, coefficients: [[f64; 2]; 5]) -> Perez {
+        Perez(coefficients.map(|[a, b]| a * turbidity + b))
+    }
+
+    fn eval(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.0;
+        let cos_gamma = f64::cos(gamma);
+        (1.0 + a * f64::exp(b / cos_theta))
+            * (1.0 + c * f64::exp(d * gamma) + e * cos_gamma * cos_gamma)
+    }
+}
+
+/// The analytic daylight model from Preetham, Shirley and Smits, "A Practical Analytic Model
+/// for Daylight" (1999), together with the sun's disk attenuated by the same atmosphere.
+pub struct PreethamSky {
+    sun_direction: Vec3,
+    sun: Option<Arc<DirectionalLight>>,
+    perez: [Perez; 3],
+    /// Zenith luminance and chromaticity divided by the Perez function towards the zenith.
+    zenith: [f64; 3],
+    exposure: f64,
+}
+
+impl PreethamSky {
+    /// Sun elevation and azimuth are in degrees, the azimuth going from +x towards +z.
+    /// Turbidity ranges from about 2 for a clear sky to 10 for a hazy one.
+    pub fn new(sun_elevation: f64, sun_azimuth: f64, turbidity: f64) -> PreethamSky {
+        PreethamSky::with_exposure(sun_elevation, sun_azimuth, turbidity, 0.035)
+    }
+
+    /// Like `new`, scaling the sky's physical luminance (in kcd/m^2) by `exposure`.
+    pub fn with_exposure(
+        sun_elevation: f64,
+        sun_azimuth: f64,
+        turbidity: f64,
+        exposure: f64,
+    ) -> PreethamSky {
+        let elevation = sun_elevation.to_radians();
+        let azimuth = sun_azimuth.to_radians();
+        let sun_direction = Vec3::new(
+            f64::cos(elevation) * f64::cos(azimuth),
+            f64::sin(elevation),
+            f64::cos(elevation) * f64::sin(azimuth),
+        );
+        // The model is only valid for the sun above the horizon.
+        let theta_s = f64::min(FRAC_PI_2 - elevation, FRAC_PI_2);
+        let t = turbidity;
+
+        let perez = [
+            Perez::new(
+                t,
+                [
+                    [0.1787, -1.4630],
+                    [-0.3554, 0.4275],
+                    [-0.0227, 5.3251],
+                    [0.1206, -2.5771],
+                    [-0.0670, 0.3703],
+                ],
+            ),
+            Perez::new(
+                t,
+                [
+                    [-0.0193, -0.2592],
+                    [-0.0665, 0.0008],
+                    [-0.0004, 0.2125],
+                    [-0.0641, -0.8989],
+                    [-0.0033, 0.0452],
+                ],
+            ),
+            Perez::new(
+                t,
+                [
+                    [-0.0167, -0.2608],
+                    [-0.0950, 0.0092],
+                    [-0.0079, 0.2102],
+                    [-0.0441, -1.6537],
+                    [-0.0109, 0.0529],
+                ],
+            ),
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * f64::tan(chi) - 0.2155 * t + 2.4192;
+        let zenith_chromaticity = |m: [[f64; 4]; 3]| {
+            let thetas = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+            let row = |r: [f64; 4]| r.iter().zip(thetas).map(|(a, b)| a * b).sum::<f64>();
+            t * t * row(m[0]) + t * row(m[1]) + row(m[2])
+        };
+        let zenith_x = zenith_chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = zenith_chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let zenith = [zenith_luminance, zenith_x, zenith_y];
+        let zenith = [0, 1, 2].map(|i| zenith[i] / perez[i].eval(1.0, theta_s));
+
+        let sun = if elevation > 0.0 {
+            let radiance = Self::sun_transmittance(theta_s, turbidity).map(|tr| tr * SUN_LUMINANCE);
+            Some(Arc::new(DirectionalLight::new(
+                sun_direction,
+                exposure * Color::new(radiance[0], radiance[1], radiance[2]),
+                SUN_ANGULAR_RADIUS,
+            )))
+        } else {
+            None
+        };
+
+        PreethamSky {
+            sun_direction,
+            sun,
+            perez,
+            zenith,
+            exposure,
+        }
+    }
+
+    /// Fraction of sunlight reaching the ground through Rayleigh and aerosol scattering, per
+    /// color channel, following the appendix of the paper.
+    fn sun_transmittance(theta_s: f64, turbidity: f64) -> [f64; 3] {
+        let relative_air_mass =
+            1.0 / (f64::cos(theta_s) + 0.15 * f64::powf(93.885 - theta_s.to_degrees(), -1.253));
+        let beta = 0.04608365 * turbidity - 0.04586025;
+        RGB_WAVELENGTHS.map(|lambda| {
+            let rayleigh = f64::exp(-0.008735 * lambda.powf(-4.08) * relative_air_mass);
+            let aerosol = f64::exp(-beta * lambda.powf(-1.3) * relative_air_mass);
+            rayleigh * aerosol
+        })
+    }
+
+    /// Radiance of the sky (without the sun's disk) along the unit vector `direction`.
+    pub fn value(&self, direction: &Vec3) -> Color {
+        // Below the horizon, keep the color of the horizon.
+        let cos_theta = f64::max(direction[1], 0.01);
+        let gamma = f64::acos(direction.dot(&self.sun_direction).clamp(-1.0, 1.0));
+        let [luminance, x, y] =
+            [0, 1, 2].map(|i| self.zenith[i] * self.perez[i].eval(cos_theta, gamma));
+
+        let luminance = self.exposure * luminance;
+        let (cx, cz) = (x / y * luminance, (1.0 - x - y) / y * luminance);
+        Color::new(
+            3.2406 * cx - 1.5372 * luminance - 0.4986 * cz,
+            -0.9689 * cx + 1.8758 * luminance + 0.0415 * cz,
+            0.0557 * cx - 0.2040 * luminance + 1.0570 * cz,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::Light;
+    use crate::point::Point;
+
+    fn assert_same(a: Color, b: Color) {
+        for i in 0..3 {
+            assert!(f64::abs(a.0[i] - b.0[i]) <= 1e-9 * f64::abs(b.0[i]));
+        }
+    }
+
+    #[test]
+    fn test_sun_matches_sky() {
+        let sky = Sky::Preetham(PreethamSky::new(30.0, 90.0, 3.0));
+        let preetham = PreethamSky::new(30.0, 90.0, 3.0);
+        let sun = sky.sun().unwrap();
+        let expected = Vec3::new(0.0, 0.5, f64::sqrt(0.75));
+
+        for _ in 0..100 {
+            let sample = sun.sample(&Point::new(0.0, 0.0, 0.0)).unwrap();
+            assert!(sample.direction.dot(&expected) >= f64::cos(SUN_ANGULAR_RADIUS) - 1e-9);
+            assert_same(sky.value(&sample.direction, true), sample.radiance);
+            assert_same(
+                sky.value(&sample.direction, false),
+                preetham.value(&sample.direction),
+            );
+        }
+
+        // Light through more atmosphere is dimmer and redder.
+        let origin = Point::new(0.0, 0.0, 0.0);
+        let low_sun = Sky::Preetham(PreethamSky::new(5.0, 90.0, 3.0))
+            .sun()
+            .unwrap();
+        let low = low_sun.sample(&origin).unwrap().radiance;
+        let high = sun.sample(&origin).unwrap().radiance;
+        assert!(low.luminance() < high.luminance());
+        assert!(low.0[2] / low.0[0] < high.0[2] / high.0[0]);
+
+        assert!(Sky::Preetham(PreethamSky::new(-5.0, 90.0, 3.0))
+            .sun()
+            .is_none());
+    }
+}
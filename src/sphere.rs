@@ -57,10 +57,30 @@ impl Sphere {
 
         (phi / (2.0 * PI), theta / PI)
     }
-}
 
-impl Hit for Sphere {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    /// Returns the derivatives of the position with respect to the coordinates of
+    /// `get_sphere_uv`, at the point with outward normal `n`.
+    fn get_sphere_partials(n: &Vec3, radius: f64) -> (Vec3, Vec3) {
+        let dpdu = 2.0 * PI * radius * Vec3::new(n[2], 0.0, -n[0]);
+
+        // The derivative along v is undefined at the poles.
+        let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - n[1] * n[1]));
+        let dpdv = if sin_theta < 1e-9 {
+            Vec3::new(0.0, 0.0, 0.0)
+        } else {
+            PI * radius
+                * Vec3::new(
+                    -n[0] * n[1] / sin_theta,
+                    sin_theta,
+                    -n[1] * n[2] / sin_theta,
+                )
+        };
+
+        (dpdu, dpdv)
+    }
+
+    /// Returns the roots of the ray with the sphere in increasing order, if any.
+    fn roots(&self, r: &Ray) -> Option<[f64; 2]> {
         let oc = *r.origin() - self.center(r.time());
         let a = r.direction().length_squared();
         let half_b = r.direction().dot(&oc);
@@ -70,19 +90,34 @@ impl Hit for Sphere {
             return None;
         }
         let sqrtd = f64::sqrt(discriminant);
-        let root =
-            get_first_in_range([(-half_b - sqrtd) / a, (-half_b + sqrtd) / a], t_min, t_max)?;
+        Some([(-half_b - sqrtd) / a, (-half_b + sqrtd) / a])
+    }
+
+    fn record(&self, r: &Ray, root: f64) -> HitRecord<'_> {
         let outward_normal = (r.at(root) - self.center(r.time())) / self.radius;
         let (u, v) = Sphere::get_sphere_uv(&Point(outward_normal));
 
-        Some(HitRecord::new(
-            r,
-            outward_normal,
-            root,
-            u,
-            v,
-            self.mat.as_ref(),
-        ))
+        let mut rec = HitRecord::new(r, outward_normal, root, u, v, self.mat.as_ref());
+        (rec.dpdu, rec.dpdv) = Sphere::get_sphere_partials(&outward_normal, self.radius);
+        // Object space is where the sphere is at the start of its motion.
+        rec.object_p = self.center_start + self.radius * outward_normal;
+        rec
+    }
+}
+
+impl Hit for Sphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let root = get_first_in_range(self.roots(r)?, t_min, t_max)?;
+        Some(self.record(r, root))
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        self.roots(r)
+            .into_iter()
+            .flatten()
+            .filter(|root| t_min <= *root && *root <= t_max)
+            .map(|root| self.record(r, root))
+            .collect()
     }
 
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
@@ -101,10 +136,5 @@ impl Hit for Sphere {
 }
 
 fn get_first_in_range<T: PartialOrd, const L: usize>(vals: [T; L], min: T, max: T) -> Option<T> {
-    for v in vals {
-        if min <= v && v <= max {
-            return Some(v);
-        }
-    }
-    None
+    vals.into_iter().find(|v| min <= *v && *v <= max)
 }
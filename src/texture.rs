@@ -1,10 +1,20 @@
 use crate::color::Color;
+use crate::hit::HitRecord;
+use crate::noise::Noise;
 use crate::perlin::Perlin;
 use crate::point::Point;
+use crate::ramp::ColorRamp;
+use crate::vec3::Vec3;
 use std::sync::Arc;
 
 pub trait Texture {
     fn value(&self, u: f64, v: f64, p: &Point) -> Color;
+
+    /// Looks the texture up at a hit, which lets textures filter over the footprint given by
+    /// the hit's differentials.
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        self.value(rec.u, rec.v, &rec.p)
+    }
 }
 
 pub struct SolidColor {
@@ -29,10 +39,23 @@ impl Texture for SolidColor {
     }
 }
 
+/// The coordinates a checker pattern is laid out in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CheckerSpace {
+    /// A 3D pattern from the sign of `sin(frequency * x) * sin(frequency * y) * sin(frequency *
+    /// z)` of the world position.
+    Position,
+    /// Squares on the `u` and `v` texture coordinates, `frequency` of them per unit, which stick
+    /// to the surface.
+    Uv,
+}
+
 #[derive(Clone)]
 pub struct CheckerTexture {
     odd: Arc<dyn Texture + Send + Sync>,
     even: Arc<dyn Texture + Send + Sync>,
+    space: CheckerSpace,
+    frequency: f64,
 }
 
 impl CheckerTexture {
@@ -43,43 +66,448 @@ impl CheckerTexture {
         Self {
             odd: Arc::new(odd),
             even: Arc::new(even),
+            space: CheckerSpace::Position,
+            frequency: 10.0,
         }
     }
 
     pub fn from_colors(even: Color, odd: Color) -> Self {
-        Self {
-            odd: Arc::new(SolidColor::new(odd)),
-            even: Arc::new(SolidColor::new(even)),
+        Self::new(SolidColor::new(even), SolidColor::new(odd))
+    }
+
+    pub fn with_space(mut self, space: CheckerSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn with_frequency(mut self, frequency: f64) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    fn is_odd(&self, u: f64, v: f64, p: &Point) -> bool {
+        let f = self.frequency;
+        match self.space {
+            CheckerSpace::Position => {
+                f64::sin(f * p.0[0]) * f64::sin(f * p.0[1]) * f64::sin(f * p.0[2]) < 0.0
+            }
+            CheckerSpace::Uv => ((f * u).floor() + (f * v).floor()) as i64 % 2 != 0,
         }
     }
 }
 
 impl Texture for CheckerTexture {
     fn value(&self, u: f64, v: f64, p: &Point) -> Color {
-        let sines = f64::sin(10.0 * p.0[0]) * f64::sin(10.0 * p.0[1]) * f64::sin(10.0 * p.0[2]);
-        if sines < 0.0 {
+        if self.is_odd(u, v, p) {
             self.odd.value(u, v, p)
         } else {
             self.even.value(u, v, p)
         }
     }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        if self.is_odd(rec.u, rec.v, &rec.p) {
+            self.odd.hit_value(rec)
+        } else {
+            self.even.hit_value(rec)
+        }
+    }
+}
+
+/// The coordinates a `TransformTexture` acts on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TextureSpace {
+    Uv,
+    /// The hit position in object space, for textures defined over space like the noise
+    /// textures. Only lookups through `hit_value` know the object space position; `value` only
+    /// has the world position to go by.
+    Object,
+}
+
+/// Scales, rotates and then offsets the coordinates `inner` is looked up with, so a scale of 2
+/// repeats the texture twice as often.
+#[derive(Clone)]
+pub struct TransformTexture {
+    inner: Arc<dyn Texture + Send + Sync>,
+    space: TextureSpace,
+    scale: Vec3,
+    axis: Vec3,
+    angle: f64,
+    offset: Vec3,
+}
+
+impl TransformTexture {
+    /// Transforms the `u` and `v` texture coordinates, rotating them by `rotation` degrees
+    /// counterclockwise.
+    pub fn uv(
+        inner: impl Texture + Send + Sync + 'static,
+        scale: (f64, f64),
+        rotation: f64,
+        offset: (f64, f64),
+    ) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            space: TextureSpace::Uv,
+            scale: Vec3::new(scale.0, scale.1, 1.0),
+            axis: Vec3::new(0.0, 0.0, 1.0),
+            angle: rotation.to_radians(),
+            offset: Vec3::new(offset.0, offset.1, 0.0),
+        }
+    }
+
+    /// Transforms the object space hit position, rotating it by `rotation` degrees around
+    /// `axis`.
+    pub fn object(
+        inner: impl Texture + Send + Sync + 'static,
+        scale: Vec3,
+        axis: Vec3,
+        rotation: f64,
+        offset: Vec3,
+    ) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            space: TextureSpace::Object,
+            scale,
+            axis: axis.unit_vector(),
+            angle: rotation.to_radians(),
+            offset,
+        }
+    }
+
+    /// Applies the scale and rotation, without the offset.
+    fn linear(&self, v: Vec3) -> Vec3 {
+        (self.scale * v).rotate(&self.axis, self.angle)
+    }
+
+    fn transform_uv(&self, u: f64, v: f64) -> (f64, f64) {
+        let uv = self.linear(Vec3::new(u, v, 0.0)) + self.offset;
+        (uv[0], uv[1])
+    }
+
+    fn transform_point(&self, p: &Point) -> Point {
+        Point(self.linear(p.0) + self.offset)
+    }
+}
+
+impl Texture for TransformTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        match self.space {
+            TextureSpace::Uv => {
+                let (u, v) = self.transform_uv(u, v);
+                self.inner.value(u, v, p)
+            }
+            TextureSpace::Object => self.inner.value(u, v, &self.transform_point(p)),
+        }
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        let mut rec = *rec;
+        match self.space {
+            TextureSpace::Uv => {
+                (rec.u, rec.v) = self.transform_uv(rec.u, rec.v);
+                // The differentials only go through the linear part of the transform.
+                let dx = self.linear(Vec3::new(rec.dudx, rec.dvdx, 0.0));
+                let dy = self.linear(Vec3::new(rec.dudy, rec.dvdy, 0.0));
+                (rec.dudx, rec.dvdx, rec.dudy, rec.dvdy) = (dx[0], dx[1], dy[0], dy[1]);
+            }
+            TextureSpace::Object => {
+                // Nested transforms keep working in the transformed space.
+                rec.object_p = self.transform_point(&rec.object_p);
+                rec.p = rec.object_p;
+            }
+        }
+        self.inner.hit_value(&rec)
+    }
+}
+
+/// Projects `inner` onto the surface along the x, y and z axes and blends the three projections
+/// by how much the normal faces each axis, for objects without texture coordinates. Each
+/// projection looks `inner` up with the two other position coordinates times `scale` as `u` and
+/// `v`. Higher `sharpness` narrows the seams where projections blend. Lookups without a hit
+/// blend the three projections equally.
+#[derive(Clone)]
+pub struct TriplanarTexture {
+    inner: Arc<dyn Texture + Send + Sync>,
+    scale: f64,
+    sharpness: f64,
+}
+
+impl TriplanarTexture {
+    pub fn new(inner: impl Texture + Send + Sync + 'static, scale: f64, sharpness: f64) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            scale,
+            sharpness,
+        }
+    }
+
+    /// The position coordinates used as `u` and `v` when projecting along each axis.
+    const PLANES: [(usize, usize); 3] = [(2, 1), (0, 2), (0, 1)];
+
+    fn weights(&self, normal: &Vec3) -> [f64; 3] {
+        let w = [0, 1, 2].map(|i| f64::abs(normal[i]).powf(self.sharpness));
+        let total = w[0] + w[1] + w[2];
+        w.map(|w| w / total)
+    }
+
+    fn blend(&self, weights: [f64; 3], lookup: impl Fn(usize, usize) -> Color) -> Color {
+        // Skip projections that barely contribute, which is most of them on flat faces, and
+        // renormalize the rest so the blend still sums to one.
+        let weights = weights.map(|w| if w > 1e-4 { w } else { 0.0 });
+        let total: f64 = weights.iter().sum();
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        for (&weight, &(a, b)) in weights.iter().zip(Self::PLANES.iter()) {
+            if weight > 0.0 {
+                color += (weight / total) * lookup(a, b);
+            }
+        }
+        color
+    }
+}
+
+impl Texture for TriplanarTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        self.blend([1.0 / 3.0; 3], |a, b| {
+            self.inner
+                .value(self.scale * p.0[a], self.scale * p.0[b], p)
+        })
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        // The screen-space derivatives of the position project like the position itself.
+        let (dpdx, dpdy) = (rec.dpdx, rec.dpdy);
+        self.blend(self.weights(&rec.normal), |a, b| {
+            let mut projected = *rec;
+            (projected.u, projected.v) = (self.scale * rec.p.0[a], self.scale * rec.p.0[b]);
+            (projected.dudx, projected.dvdx) = (self.scale * dpdx[a], self.scale * dpdx[b]);
+            (projected.dudy, projected.dvdy) = (self.scale * dpdy[a], self.scale * dpdy[b]);
+            self.inner.hit_value(&projected)
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NoiseMode {
+    /// Perlin noise remapped to `[0, 1]`.
+    Plain,
+    /// Several octaves of noise, for a rougher look.
+    Turbulence,
+    /// A sine wave along z whose phase is perturbed by turbulence, giving marble-like veins.
+    Marble,
 }
 
 #[derive(Clone)]
 pub struct NoiseTexture {
     noise: Perlin,
+    scale: f64,
+    mode: NoiseMode,
 }
 
 impl NoiseTexture {
-    pub fn new() -> NoiseTexture {
-        NoiseTexture {
-            noise: Perlin::new(),
-        }
+    /// `scale` is the frequency of the noise in world space.
+    pub fn with_noise(noise: Perlin, scale: f64, mode: NoiseMode) -> NoiseTexture {
+        NoiseTexture { noise, scale, mode }
     }
 }
 
 impl Texture for NoiseTexture {
     fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
-        self.noise.noise(p) * Color::new(1.0, 1.0, 1.0)
+        let scaled = Point(self.scale * p.0);
+        let value = match self.mode {
+            NoiseMode::Plain => 0.5 * (1.0 + self.noise.noise(&scaled)),
+            NoiseMode::Turbulence => self.noise.turb(&scaled, 7),
+            NoiseMode::Marble => 0.5 * (1.0 + f64::sin(scaled.0[2] + 10.0 * self.noise.turb(p, 7))),
+        };
+        value * Color::new(1.0, 1.0, 1.0)
+    }
+}
+
+/// The scalar a `RampTexture` maps through its ramp.
+#[derive(Clone)]
+pub enum RampInput {
+    /// The luminance of another texture.
+    Texture(Arc<dyn Texture + Sync + Send>),
+    /// Any scalar `Noise` at `scale` times the position, e.g. `Worley` cells for stones and
+    /// scales or a `RidgedMultifractal` for terrain.
+    Noise {
+        noise: Arc<dyn Noise + Send + Sync>,
+        scale: f64,
+    },
+    /// The height `p.y`, mapped from `[min, max]` onto `[0, 1]`.
+    Height { min: f64, max: f64 },
+    /// The cosine between the normal and the direction towards the viewer, which is 1 where the
+    /// surface faces the viewer and falls to 0 at its silhouette. This needs the normal and
+    /// the ray of a hit, so it only works through `hit_value`; `value` always uses 1.0.
+    FacingRatio,
+}
+
+/// Colors a scalar input through a `ColorRamp`, so gradients, bands and tints can be put on any
+/// texture or geometric attribute.
+#[derive(Clone)]
+pub struct RampTexture {
+    input: RampInput,
+    ramp: ColorRamp,
+}
+
+impl RampTexture {
+    pub fn new(input: RampInput, ramp: ColorRamp) -> Self {
+        Self { input, ramp }
+    }
+
+    pub fn from_texture(texture: impl Texture + Sync + Send + 'static, ramp: ColorRamp) -> Self {
+        Self::new(RampInput::Texture(Arc::new(texture)), ramp)
+    }
+
+    /// `scale` is the frequency of the noise in world space.
+    pub fn from_noise(
+        noise: impl Noise + Send + Sync + 'static,
+        scale: f64,
+        ramp: ColorRamp,
+    ) -> Self {
+        Self::new(
+            RampInput::Noise {
+                noise: Arc::new(noise),
+                scale,
+            },
+            ramp,
+        )
+    }
+
+    fn height(p: &Point, min: f64, max: f64) -> f64 {
+        (p.0[1] - min) / (max - min)
+    }
+}
+
+impl Texture for RampTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        let t = match &self.input {
+            RampInput::Texture(texture) => texture.value(u, v, p).luminance(),
+            RampInput::Noise { noise, scale } => noise.noise(&Point(*scale * p.0)),
+            RampInput::Height { min, max } => Self::height(p, *min, *max),
+            RampInput::FacingRatio => 1.0,
+        };
+        self.ramp.eval(t)
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        let t = match &self.input {
+            RampInput::Texture(texture) => texture.hit_value(rec).luminance(),
+            RampInput::Noise { noise, scale } => noise.noise(&Point(*scale * rec.p.0)),
+            RampInput::Height { min, max } => Self::height(&rec.p, *min, *max),
+            RampInput::FacingRatio => f64::abs(rec.normal.dot(&rec.incoming.unit_vector())),
+        };
+        self.ramp.eval(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::ray::Ray;
+
+    /// Returns the texture coordinates it is looked up with as a color.
+    struct UvProbe;
+
+    impl Texture for UvProbe {
+        fn value(&self, u: f64, v: f64, _: &Point) -> Color {
+            Color::new(u, v, 0.0)
+        }
+    }
+
+    /// Returns the position it is looked up with as a color.
+    struct PositionProbe;
+
+    impl Texture for PositionProbe {
+        fn value(&self, _: f64, _: f64, p: &Point) -> Color {
+            Color(p.0)
+        }
+    }
+
+    /// Returns the screen-space derivatives along x of the texture coordinates it is looked up
+    /// with as a color.
+    struct DifferentialProbe;
+
+    impl Texture for DifferentialProbe {
+        fn value(&self, _: f64, _: f64, _: &Point) -> Color {
+            Color::new(0.0, 0.0, 0.0)
+        }
+
+        fn hit_value(&self, rec: &HitRecord) -> Color {
+            Color::new(rec.dudx, rec.dvdx, 0.0)
+        }
+    }
+
+    fn assert_near(color: Color, expected: [f64; 3]) {
+        for (i, e) in expected.into_iter().enumerate() {
+            assert!(f64::abs(color.0[i] - e) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_uv_checker() {
+        let checker =
+            CheckerTexture::from_colors(Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0))
+                .with_space(CheckerSpace::Uv)
+                .with_frequency(2.0);
+        let origin = Point::new(0.0, 0.0, 0.0);
+        assert_eq!(checker.value(0.1, 0.1, &origin).0[0], 0.0);
+        assert_eq!(checker.value(0.6, 0.1, &origin).0[0], 1.0);
+        assert_eq!(checker.value(0.6, 0.6, &origin).0[0], 0.0);
+        // The squares carry on past the edges of the texture coordinates.
+        assert_eq!(checker.value(-0.1, 0.1, &origin).0[0], 1.0);
+        assert_eq!(checker.value(1.1, 0.1, &origin).0[0], 0.0);
+    }
+
+    #[test]
+    fn test_transform_mapping() {
+        let origin = Point::new(0.0, 0.0, 0.0);
+        let uv = TransformTexture::uv(UvProbe, (2.0, 2.0), 90.0, (0.5, 0.0));
+        assert_near(uv.value(1.0, 0.0, &origin), [0.5, 2.0, 0.0]);
+        assert_near(uv.value(0.0, 1.0, &origin), [-1.5, 0.0, 0.0]);
+
+        // The differentials are scaled and rotated but not offset.
+        let mat = Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+        let r = Ray::new(Point::new(5.0, 5.0, 5.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let mut rec = HitRecord::new(&r, Vec3::new(0.0, 1.0, 0.0), 1.0, 1.0, 0.0, &mat);
+        (rec.dudx, rec.dvdx) = (0.1, 0.0);
+        let differentials = TransformTexture::uv(DifferentialProbe, (2.0, 2.0), 90.0, (0.5, 0.0));
+        assert_near(uv.hit_value(&rec), [0.5, 2.0, 0.0]);
+        assert_near(differentials.hit_value(&rec), [0.0, 0.2, 0.0]);
+
+        // Object space lookups follow the object rather than its position in the world.
+        rec.object_p = Point::new(1.0, 1.0, 0.0);
+        let object = TransformTexture::object(
+            PositionProbe,
+            Vec3::new(1.0, 2.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        assert_near(object.hit_value(&rec), [0.0, 2.0, 0.0]);
+        assert_near(object.value(0.0, 0.0, &rec.p), [5.0, 8.0, -4.0]);
+    }
+
+    #[test]
+    fn test_triplanar_weights() {
+        let triplanar = TriplanarTexture::new(UvProbe, 2.0, 1.0);
+        let weights = triplanar.weights(&Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(weights, [0.0, 1.0, 0.0]);
+        let diagonal = Vec3::new(1.0, 1.0, 0.0).unit_vector();
+        let [x, y, z] = triplanar.weights(&diagonal);
+        assert_near(Color::new(x, y, z), [0.5, 0.5, 0.0]);
+
+        // Higher sharpness favors the axis the normal faces the most.
+        let sharp = TriplanarTexture::new(UvProbe, 2.0, 4.0);
+        let weights = sharp.weights(&Vec3::new(0.8, 0.6, 0.0));
+        let expected = 0.8f64.powi(4) / (0.8f64.powi(4) + 0.6f64.powi(4));
+        assert!(f64::abs(weights[0] - expected) < 1e-9);
+        assert!(f64::abs(weights.iter().sum::<f64>() - 1.0) < 1e-9);
+
+        // A face looking down the y axis projects the x and z coordinates.
+        let mat = Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+        let r = Ray::new(Point::new(0.25, 2.0, 0.5), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = HitRecord::new(&r, Vec3::new(0.0, 1.0, 0.0), 1.0, 0.0, 0.0, &mat);
+        assert_near(triplanar.hit_value(&rec), [0.5, 1.0, 0.0]);
     }
 }
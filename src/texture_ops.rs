@@ -0,0 +1,282 @@
+//! Textures that combine other textures, for building shading networks out of the basic ones.
+//! Scalars are passed around as gray colors, and inputs used as scalars are read by their
+//! luminance.
+
+use crate::color::Color;
+use crate::hit::HitRecord;
+use crate::point::Point;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+type TextureRef = Arc<dyn Texture + Sync + Send>;
+
+fn gray(value: f64) -> Color {
+    Color::new(value, value, value)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MathOp {
+    Add,
+    Multiply,
+}
+
+/// Combines two textures channel by channel.
+#[derive(Clone)]
+pub struct MathTexture {
+    a: TextureRef,
+    b: TextureRef,
+    op: MathOp,
+}
+
+impl MathTexture {
+    pub fn new(
+        a: impl Texture + Sync + Send + 'static,
+        b: impl Texture + Sync + Send + 'static,
+        op: MathOp,
+    ) -> Self {
+        Self {
+            a: Arc::new(a),
+            b: Arc::new(b),
+            op,
+        }
+    }
+
+    fn eval(&self, lookup: impl Fn(&TextureRef) -> Color) -> Color {
+        let (a, b) = (lookup(&self.a).0, lookup(&self.b).0);
+        Color(match self.op {
+            MathOp::Add => a + b,
+            MathOp::Multiply => a * b,
+        })
+    }
+}
+
+impl Texture for MathTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.eval(|texture| texture.value(u, v, p))
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        self.eval(|texture| texture.hit_value(rec))
+    }
+}
+
+/// Blends from `a` where `factor` is 0 to `b` where it is 1.
+#[derive(Clone)]
+pub struct MixTexture {
+    a: TextureRef,
+    b: TextureRef,
+    factor: TextureRef,
+}
+
+impl MixTexture {
+    pub fn new(
+        a: impl Texture + Sync + Send + 'static,
+        b: impl Texture + Sync + Send + 'static,
+        factor: impl Texture + Sync + Send + 'static,
+    ) -> Self {
+        Self {
+            a: Arc::new(a),
+            b: Arc::new(b),
+            factor: Arc::new(factor),
+        }
+    }
+
+    fn eval(&self, lookup: impl Fn(&TextureRef) -> Color) -> Color {
+        let t = lookup(&self.factor).luminance().clamp(0.0, 1.0);
+        // Skip the lookup of an input that does not contribute.
+        if t == 0.0 {
+            return lookup(&self.a);
+        }
+        if t == 1.0 {
+            return lookup(&self.b);
+        }
+        (1.0 - t) * lookup(&self.a) + t * lookup(&self.b)
+    }
+}
+
+impl Texture for MixTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.eval(|texture| texture.value(u, v, p))
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        self.eval(|texture| texture.hit_value(rec))
+    }
+}
+
+/// One minus the inner texture.
+#[derive(Clone)]
+pub struct InvertTexture {
+    inner: TextureRef,
+}
+
+impl InvertTexture {
+    pub fn new(inner: impl Texture + Sync + Send + 'static) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    fn invert(c: Color) -> Color {
+        Color(Vec3::new(1.0, 1.0, 1.0) - c.0)
+    }
+}
+
+impl Texture for InvertTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        Self::invert(self.inner.value(u, v, p))
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        Self::invert(self.inner.hit_value(rec))
+    }
+}
+
+/// Clamps each channel of the inner texture to `[min, max]`.
+#[derive(Clone)]
+pub struct ClampTexture {
+    inner: TextureRef,
+    min: f64,
+    max: f64,
+}
+
+impl ClampTexture {
+    pub fn new(inner: impl Texture + Sync + Send + 'static, min: f64, max: f64) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            min,
+            max,
+        }
+    }
+
+    fn clamp(&self, c: Color) -> Color {
+        let clamp = |x: f64| x.clamp(self.min, self.max);
+        Color::new(clamp(c.0[0]), clamp(c.0[1]), clamp(c.0[2]))
+    }
+}
+
+impl Texture for ClampTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.clamp(self.inner.value(u, v, p))
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        self.clamp(self.inner.hit_value(rec))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Luminance,
+}
+
+/// Extracts one channel of a color texture as a scalar.
+#[derive(Clone)]
+pub struct ChannelTexture {
+    inner: TextureRef,
+    channel: Channel,
+}
+
+impl ChannelTexture {
+    pub fn new(inner: impl Texture + Sync + Send + 'static, channel: Channel) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            channel,
+        }
+    }
+
+    fn extract(&self, c: Color) -> Color {
+        gray(match self.channel {
+            Channel::Red => c.0[0],
+            Channel::Green => c.0[1],
+            Channel::Blue => c.0[2],
+            Channel::Luminance => c.luminance(),
+        })
+    }
+}
+
+impl Texture for ChannelTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.extract(self.inner.value(u, v, p))
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        self.extract(self.inner.hit_value(rec))
+    }
+}
+
+/// Builds a color from three scalar textures, one per channel.
+#[derive(Clone)]
+pub struct CombineTexture {
+    red: TextureRef,
+    green: TextureRef,
+    blue: TextureRef,
+}
+
+impl CombineTexture {
+    pub fn new(
+        red: impl Texture + Sync + Send + 'static,
+        green: impl Texture + Sync + Send + 'static,
+        blue: impl Texture + Sync + Send + 'static,
+    ) -> Self {
+        Self {
+            red: Arc::new(red),
+            green: Arc::new(green),
+            blue: Arc::new(blue),
+        }
+    }
+
+    fn eval(&self, lookup: impl Fn(&TextureRef) -> Color) -> Color {
+        Color::new(
+            lookup(&self.red).luminance(),
+            lookup(&self.green).luminance(),
+            lookup(&self.blue).luminance(),
+        )
+    }
+}
+
+impl Texture for CombineTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.eval(|texture| texture.value(u, v, p))
+    }
+
+    fn hit_value(&self, rec: &HitRecord) -> Color {
+        self.eval(|texture| texture.hit_value(rec))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::SolidColor;
+
+    fn assert_near(color: Color, expected: [f64; 3]) {
+        for (i, e) in expected.into_iter().enumerate() {
+            assert!(f64::abs(color.0[i] - e) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_combinators() {
+        let p = Point::new(0.0, 0.0, 0.0);
+        let orange = || SolidColor::from_rgb(1.0, 0.5, 0.0);
+        let half = || SolidColor::from_rgb(0.5, 0.5, 0.5);
+
+        let product = MathTexture::new(orange(), half(), MathOp::Multiply);
+        assert_near(product.value(0.0, 0.0, &p), [0.5, 0.25, 0.0]);
+
+        let mixed = MixTexture::new(orange(), InvertTexture::new(orange()), half());
+        assert_near(mixed.value(0.0, 0.0, &p), [0.5, 0.5, 0.5]);
+
+        let green = ChannelTexture::new(orange(), Channel::Green);
+        let clamped =
+            ClampTexture::new(MathTexture::new(orange(), orange(), MathOp::Add), 0.0, 1.0);
+        assert_near(clamped.value(0.0, 0.0, &p), [1.0, 1.0, 0.0]);
+        let combined = CombineTexture::new(green, clamped, SolidColor::from_rgb(0.0, 0.0, 0.0));
+        assert_near(combined.value(0.0, 0.0, &p), [0.5, 0.2126 + 0.7152, 0.0]);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::material::Material;
+use crate::point::Point;
+use crate::quartic::solve_quartic;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+
+/// A ring lying in the xz plane around `center`, made of a tube of `minor_radius` swept along a
+/// circle of `major_radius`. `u` runs around the ring and `v` around the tube, starting at its
+/// outer equator.
+pub struct Torus {
+    center: Point,
+    major_radius: f64,
+    minor_radius: f64,
+    mat: Box<dyn Material + Sync + Send>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point,
+        major_radius: f64,
+        minor_radius: f64,
+        mat: impl Material + Sync + Send + 'static,
+    ) -> Torus {
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+            mat: Box::new(mat),
+        }
+    }
+
+    fn get_torus_uv(&self, p: &Point) -> (f64, f64) {
+        let phi = f64::atan2(p.0[2], p.0[0]);
+        let radial = f64::sqrt(p.0[0] * p.0[0] + p.0[2] * p.0[2]) - self.major_radius;
+        let theta = f64::atan2(p.0[1], radial);
+        let wrap = |angle: f64| if angle < 0.0 { angle + 2.0 * PI } else { angle };
+        (wrap(phi) / (2.0 * PI), wrap(theta) / (2.0 * PI))
+    }
+}
+
+impl Hit for Torus {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (big_r, small_r) = (self.major_radius, self.minor_radius);
+        let length = r.direction().length();
+        let d = *r.direction() / length;
+
+        // Start the ray next to the torus to keep the quartic coefficients small for distant
+        // rays, which would otherwise lose the roots to cancellation.
+        let oc = *r.origin() - self.center;
+        let start = f64::max(0.0, oc.length() - (big_r + small_r) - 1.0);
+        let o = oc + start * d;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2) along p = o + s d with |d| = 1.
+        let f = o.dot(&d);
+        let e = o.length_squared() + big_r * big_r - small_r * small_r;
+        let four_r2 = 4.0 * big_r * big_r;
+        let roots = solve_quartic(
+            1.0,
+            4.0 * f,
+            4.0 * f * f + 2.0 * e - four_r2 * (d[0] * d[0] + d[2] * d[2]),
+            4.0 * f * e - 2.0 * four_r2 * (o[0] * d[0] + o[2] * d[2]),
+            e * e - four_r2 * (o[0] * o[0] + o[2] * o[2]),
+        );
+        let t = roots
+            .into_iter()
+            .map(|s| (start + s) / length)
+            .find(|t| t_min <= *t && *t <= t_max)?;
+
+        let p = Point(r.at(t) - self.center);
+        // The normal points away from the center of the tube under the hit point.
+        let phi = f64::atan2(p.0[2], p.0[0]);
+        let tube_center = Vec3::new(big_r * f64::cos(phi), 0.0, big_r * f64::sin(phi));
+        let outward_normal = (p.0 - tube_center).unit_vector();
+        let (u, v) = self.get_torus_uv(&p);
+
+        let mut rec = HitRecord::new(r, outward_normal, t, u, v, self.mat.as_ref());
+        rec.dpdu = 2.0 * PI * Vec3::new(-p.0[2], 0.0, p.0[0]);
+        let theta = 2.0 * PI * v;
+        rec.dpdv = 2.0
+            * PI
+            * small_r
+            * Vec3::new(
+                -f64::sin(theta) * f64::cos(phi),
+                f64::cos(theta),
+                -f64::sin(theta) * f64::sin(phi),
+            );
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AABB> {
+        let extent = self.major_radius + self.minor_radius;
+        let half = Vec3::new(extent, self.minor_radius, extent);
+        Some(AABB::new(self.center - half, self.center + half))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn test_torus_hit() {
+        let torus = Torus::new(
+            Point::new(0.0, 0.0, 0.0),
+            2.0,
+            0.5,
+            Lambertian::from_color(Color::new(0.5, 0.5, 0.5)),
+        );
+
+        // Straight down through the tube.
+        let r = Ray::new(Point::new(2.0, 10.0, 0.0), Vec3::new(0.0, -2.0, 0.0), 0.0);
+        let rec = torus.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 4.75) < 1e-9);
+        assert!(f64::abs(rec.normal[1] - 1.0) < 1e-9);
+        assert!(f64::abs(rec.v - 0.25) < 1e-9);
+
+        // Through the hole.
+        let r = Ray::new(Point::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(torus.hit(&r, 0.001, f64::INFINITY).is_none());
+
+        // Along the x axis from far away, hitting the outer rim first.
+        let r = Ray::new(Point::new(1000.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        let rec = torus.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 997.5) < 1e-9);
+    }
+}
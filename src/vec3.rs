@@ -54,8 +54,30 @@ impl Vec3 {
         r_out_perpendicular + r_out_parallel
     }
 
+    /// Rotates `self` by `angle` radians around the unit vector `axis`.
+    pub fn rotate(&self, axis: &Vec3, angle: f64) -> Vec3 {
+        let (sin, cos) = f64::sin_cos(angle);
+        cos * *self + sin * axis.cross(self) + (1.0 - cos) * axis.dot(self) * *axis
+    }
+
+    /// Returns two unit vectors that together with `self` (assumed to be unit length) form an
+    /// orthonormal basis.
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let a = if f64::abs(self.e[0]) > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t = self.cross(&a).unit_vector();
+        let b = self.cross(&t);
+        (t, b)
+    }
+
     pub fn random_in_unit_sphere() -> Point {
-        let mut rng = rand::thread_rng();
+        Self::random_in_unit_sphere_with(&mut rand::thread_rng())
+    }
+
+    pub fn random_in_unit_sphere_with<R: Rng + ?Sized>(rng: &mut R) -> Point {
         loop {
             let p = Vec3::new(
                 rng.gen_range(-1.0..1.0),
@@ -72,6 +94,10 @@ impl Vec3 {
         Self::random_in_unit_sphere().0.unit_vector()
     }
 
+    pub fn random_unit_vector_with<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+        Self::random_in_unit_sphere_with(rng).0.unit_vector()
+    }
+
     pub fn random_in_unit_disk() -> Point {
         let mut rng = rand::thread_rng();
         loop {
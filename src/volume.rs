@@ -0,0 +1,302 @@
+use crate::aabb::AABB;
+use crate::hit::{Hit, HitRecord};
+use crate::material::Material;
+use crate::perlin::Perlin;
+use crate::point::Point;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use rand::Rng;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub trait DensityField {
+    fn density(&self, p: &Point) -> f64;
+    /// An upper bound of `density` over the whole field, used as the majorant for tracking.
+    fn max_density(&self) -> f64;
+}
+
+/// A density grid of `nx * ny * nz` voxels stretched over `bounds`, with x varying fastest.
+/// Density is trilinearly interpolated between voxel centers and is zero outside of `bounds`.
+pub struct DensityGrid {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    data: Vec<f64>,
+    bounds: AABB,
+    max: f64,
+}
+
+impl DensityGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, data: Vec<f64>, bounds: AABB) -> Self {
+        assert_eq!(data.len(), nx * ny * nz, "Density grid size mismatch.");
+        let max = data.iter().cloned().fold(0.0, f64::max);
+        Self {
+            nx,
+            ny,
+            nz,
+            data,
+            bounds,
+            max,
+        }
+    }
+
+    /// Loads a raw density grid: three little-endian `u32`s `nx`, `ny`, `nz` followed by
+    /// `nx * ny * nz` little-endian `f32` densities.
+    pub fn load_raw(path: impl AsRef<Path>, bounds: AABB) -> io::Result<Self> {
+        Self::from_raw_bytes(&fs::read(path)?, bounds)
+    }
+
+    pub fn from_raw_bytes(bytes: &[u8], bounds: AABB) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        if bytes.len() < 12 {
+            return Err(invalid("Density grid header is truncated."));
+        }
+        let dim = |i: usize| {
+            u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]) as usize
+        };
+        let (nx, ny, nz) = (dim(0), dim(4), dim(8));
+        if nx == 0 || ny == 0 || nz == 0 {
+            return Err(invalid("Density grid has an empty dimension."));
+        }
+
+        let size = nx
+            .checked_mul(ny)
+            .and_then(|n| n.checked_mul(nz))
+            .and_then(|n| n.checked_mul(4))
+            .ok_or_else(|| invalid("Density grid size overflows."))?;
+        let body = &bytes[12..];
+        if body.len() != size {
+            return Err(invalid("Density grid size does not match its header."));
+        }
+        let data = body
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]) as f64)
+            .collect();
+
+        Ok(Self::new(nx, ny, nz, data, bounds))
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.data[(z * self.ny + y) * self.nx + x]
+    }
+}
+
+impl DensityField for DensityGrid {
+    fn density(&self, p: &Point) -> f64 {
+        let dims = [self.nx, self.ny, self.nz];
+        let mut lo = [0; 3];
+        let mut hi = [0; 3];
+        let mut frac = [0.0; 3];
+
+        for i in 0..3 {
+            let extent = self.bounds.maximum.0[i] - self.bounds.minimum.0[i];
+            let rel = (p.0[i] - self.bounds.minimum.0[i]) / extent;
+            if !(0.0..=1.0).contains(&rel) {
+                return 0.0;
+            }
+            let x = (rel * dims[i] as f64 - 0.5).clamp(0.0, (dims[i] - 1) as f64);
+            lo[i] = x.floor() as usize;
+            hi[i] = usize::min(lo[i] + 1, dims[i] - 1);
+            frac[i] = x - lo[i] as f64;
+        }
+
+        let mut res = 0.0;
+        for (dx, wx) in [(lo[0], 1.0 - frac[0]), (hi[0], frac[0])] {
+            for (dy, wy) in [(lo[1], 1.0 - frac[1]), (hi[1], frac[1])] {
+                for (dz, wz) in [(lo[2], 1.0 - frac[2]), (hi[2], frac[2])] {
+                    res += wx * wy * wz * self.voxel(dx, dy, dz);
+                }
+            }
+        }
+        res
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max
+    }
+}
+
+/// Procedural density from Perlin turbulence, scaled to at most `density`.
+pub struct PerlinDensity {
+    noise: Perlin,
+    scale: f64,
+    density: f64,
+}
+
+impl PerlinDensity {
+    pub fn with_noise(noise: Perlin, scale: f64, density: f64) -> Self {
+        Self {
+            noise,
+            scale,
+            density,
+        }
+    }
+}
+
+impl DensityField for PerlinDensity {
+    fn density(&self, p: &Point) -> f64 {
+        self.density * self.noise.turb(&Point(self.scale * p.0), 7).min(1.0)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.density
+    }
+}
+
+/// A participating medium with spatially varying density, filling the inside of a closed
+/// `boundary`. Scattering distances are sampled with delta tracking against the field's
+/// majorant, so the scattered radiance is unbiased regardless of how the density varies.
+pub struct HeterogeneousMedium {
+    boundary: Box<dyn Hit + Sync + Send>,
+    density: Box<dyn DensityField + Sync + Send>,
+    phase_function: Box<dyn Material + Sync + Send>,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        boundary: impl Hit + Sync + Send + 'static,
+        density: impl DensityField + Sync + Send + 'static,
+        phase_function: impl Material + Sync + Send + 'static,
+    ) -> Self {
+        Self {
+            boundary: Box::new(boundary),
+            density: Box::new(density),
+            phase_function: Box::new(phase_function),
+        }
+    }
+
+    /// Returns the ray parameters where `r` enters and leaves the boundary, clipped to
+    /// `[t_min, t_max]`.
+    fn segment(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let rec1 = self.boundary.hit(r, f64::NEG_INFINITY, f64::INFINITY)?;
+        let rec2 = self.boundary.hit(r, rec1.t + 0.0001, f64::INFINITY)?;
+
+        let t0 = f64::max(f64::max(rec1.t, t_min), 0.0);
+        let t1 = f64::min(rec2.t, t_max);
+        if t0 >= t1 {
+            return None;
+        }
+        Some((t0, t1))
+    }
+}
+
+impl Hit for HeterogeneousMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let majorant = self.density.max_density();
+        if majorant <= 0.0 {
+            return None;
+        }
+        let (mut t, t1) = self.segment(r, t_min, t_max)?;
+
+        let mut rng = rand::thread_rng();
+        let step = 1.0 / (majorant * r.direction().length());
+        loop {
+            t -= (1.0 - rng.gen::<f64>()).ln() * step;
+            if t >= t1 {
+                return None;
+            }
+            if self.density.density(&r.at(t)) / majorant > rng.gen() {
+                // The normal is arbitrary inside a medium.
+                return Some(HitRecord::new(
+                    r,
+                    Vec3::new(1.0, 0.0, 0.0),
+                    t,
+                    0.0,
+                    0.0,
+                    self.phase_function.as_ref(),
+                ));
+            }
+        }
+    }
+
+    /// Estimates the fraction of light that passes through the medium with ratio tracking,
+    /// which weighs the tentative collisions by how likely they are instead of stopping at one,
+    /// giving shadows far less noise than the all or nothing answer of `hit`.
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let majorant = self.density.max_density();
+        let (mut t, t1) = match self.segment(r, t_min, t_max) {
+            Some(segment) if majorant > 0.0 => segment,
+            _ => return 1.0,
+        };
+
+        let mut rng = rand::thread_rng();
+        let step = 1.0 / (majorant * r.direction().length());
+        let mut tr = 1.0;
+        loop {
+            t -= (1.0 - rng.gen::<f64>()).ln() * step;
+            if t >= t1 {
+                return tr;
+            }
+            tr *= 1.0 - self.density.density(&r.at(t)) / majorant;
+        }
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AABB> {
+        self.boundary.bounding_box(start_time, end_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cuboid::Cuboid;
+    use crate::hit::HitList;
+    use crate::material::{HenyeyGreenstein, Lambertian};
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn test_density_grid_from_raw_bytes() {
+        let mut bytes = Vec::new();
+        for dim in [2u32, 1, 1] {
+            bytes.extend_from_slice(&dim.to_le_bytes());
+        }
+        for density in [0.0f32, 1.0] {
+            bytes.extend_from_slice(&density.to_le_bytes());
+        }
+
+        let bounds = AABB::new(Point::new(0.0, 0.0, 0.0), Point::new(2.0, 1.0, 1.0));
+        let grid = DensityGrid::from_raw_bytes(&bytes, bounds.clone()).unwrap();
+        assert_eq!(grid.max_density(), 1.0);
+        assert_eq!(grid.density(&Point::new(1.0, 0.5, 0.5)), 0.5);
+        assert_eq!(grid.density(&Point::new(3.0, 0.5, 0.5)), 0.0);
+
+        assert!(DensityGrid::from_raw_bytes(&bytes[..14], bounds.clone()).is_err());
+
+        // Empty and overflowing dimensions are rejected rather than panicking.
+        let header =
+            |dims: [u32; 3]| -> Vec<u8> { dims.iter().flat_map(|dim| dim.to_le_bytes()).collect() };
+        let empty = DensityGrid::from_raw_bytes(&header([0, 1, 1]), bounds.clone());
+        assert_eq!(empty.err().unwrap().kind(), io::ErrorKind::InvalidData);
+        let huge = DensityGrid::from_raw_bytes(&header([u32::MAX; 3]), bounds);
+        assert_eq!(huge.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_ratio_tracking_transmittance() {
+        let (minimum, maximum) = (Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 1.0));
+        let gray = || Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+        let medium = HeterogeneousMedium::new(
+            Cuboid::new(minimum, maximum, gray()),
+            DensityGrid::new(1, 1, 1, vec![1.0], AABB::new(minimum, maximum)),
+            HenyeyGreenstein::from_color(Color::new(0.5, 0.5, 0.5), 0.0),
+        );
+
+        // A unit of density over a unit of distance, whatever the length of the direction.
+        let r = Ray::new(Point::new(-1.0, 0.5, 0.5), Vec3::new(2.0, 0.0, 0.0), 0.0);
+        let n = 20000;
+        let mean = (0..n)
+            .map(|_| medium.transmittance(&r, 0.001, f64::INFINITY))
+            .sum::<f64>()
+            / n as f64;
+        assert!(f64::abs(mean - f64::exp(-1.0)) < 0.02);
+
+        // Solid objects in front of the medium block the shadow ray altogether.
+        let mut world = HitList::new();
+        world.push(medium);
+        world.push(Sphere::new(Point::new(-0.5, 0.5, 0.5), 0.1, gray()));
+        assert_eq!(world.transmittance(&r, 0.001, f64::INFINITY), 0.0);
+    }
+}
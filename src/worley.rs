@@ -0,0 +1,114 @@
+use crate::noise::Noise;
+use crate::point::Point;
+use crate::vec3::Vec3;
+use rand::Rng;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WorleyMode {
+    /// Distance to the closest feature point, giving round cells.
+    F1,
+    /// Distance to the second closest feature point.
+    F2,
+    /// Difference of the two, which is zero on the borders between cells, giving cracks.
+    F2MinusF1,
+}
+
+/// Cellular noise from Worley, "A Cellular Texture Basis Function" (1996). Space is divided into
+/// unit cells each holding one feature point at a pseudo-random position, and the noise is based
+/// on the distances to the closest feature points.
+#[derive(Clone)]
+pub struct Worley {
+    seed: u64,
+    mode: WorleyMode,
+}
+
+impl Worley {
+    pub fn with_rng<R: Rng + ?Sized>(rng: &mut R, mode: WorleyMode) -> Worley {
+        Worley {
+            seed: rng.gen(),
+            mode,
+        }
+    }
+
+    /// Returns the distances to the closest and second closest feature points.
+    pub fn distances(&self, p: &Point) -> (f64, f64) {
+        let cell = [p.0[0].floor(), p.0[1].floor(), p.0[2].floor()];
+
+        let mut f1 = f64::INFINITY;
+        let mut f2 = f64::INFINITY;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let neighbor = [
+                        cell[0] as i64 + dx,
+                        cell[1] as i64 + dy,
+                        cell[2] as i64 + dz,
+                    ];
+                    let feature = self.feature_point(neighbor);
+                    let distance = (feature - *p).length();
+                    if distance < f1 {
+                        f2 = f1;
+                        f1 = distance;
+                    } else if distance < f2 {
+                        f2 = distance;
+                    }
+                }
+            }
+        }
+        (f1, f2)
+    }
+
+    fn feature_point(&self, cell: [i64; 3]) -> Point {
+        let mut h = self.seed;
+        for c in cell {
+            h = splitmix64(h ^ c as u64);
+        }
+        let offset = |h: u64| (h >> 11) as f64 / (1u64 << 53) as f64;
+        let (h1, h2, h3) = (h, splitmix64(h), splitmix64(splitmix64(h)));
+
+        Point(
+            Vec3::new(cell[0] as f64, cell[1] as f64, cell[2] as f64)
+                + Vec3::new(offset(h1), offset(h2), offset(h3)),
+        )
+    }
+}
+
+impl Noise for Worley {
+    fn noise(&self, p: &Point) -> f64 {
+        let (f1, f2) = self.distances(p);
+        match self.mode {
+            WorleyMode::F1 => f1,
+            WorleyMode::F2 => f2,
+            WorleyMode::F2MinusF1 => f2 - f1,
+        }
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_distances_are_ordered() {
+        let worley = Worley::with_rng(&mut ChaCha8Rng::seed_from_u64(3), WorleyMode::F2MinusF1);
+        for p in [
+            Point::new(0.5, 0.5, 0.5),
+            Point::new(-3.2, 7.9, 1.1),
+            Point::new(100.0, -0.25, 12.75),
+        ] {
+            let (f1, f2) = worley.distances(&p);
+            assert!(0.0 <= f1 && f1 <= f2);
+            assert!(f1 < f64::sqrt(3.0));
+            assert_eq!(worley.noise(&p), f2 - f1);
+        }
+    }
+}